@@ -0,0 +1,188 @@
+use crate::rangeset::{Countable, Discrete, RangeSet};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// How many of a collection of possibly overlapping ranges cover each value.
+///
+/// Built by sweeping through the range boundaries once, it stores the depth as a step
+/// function: each step holds from its position up to the next one, the last up to
+/// `T::MAX`, and values before the first step aren't covered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coverage<T> {
+    steps: Vec<(T, usize)>,
+}
+
+impl<T: Discrete> Coverage<T> {
+    pub fn new(ranges: impl IntoIterator<Item = RangeInclusive<T>>) -> Self {
+        // How many ranges start and stop at each position.
+        let mut events = BTreeMap::<T, (usize, usize)>::new();
+
+        for range in ranges.into_iter().filter(|range| !range.is_empty()) {
+            events.entry(*range.start()).or_default().0 += 1;
+
+            if let Some(end) = range.end().successor() {
+                events.entry(end).or_default().1 += 1;
+            }
+        }
+
+        let mut steps: Vec<(T, usize)> = Vec::new();
+        let mut depth = 0;
+
+        for (pos, (opened, closed)) in events {
+            depth = depth + opened - closed;
+
+            if steps.last().map_or(0, |(_, last)| *last) != depth {
+                steps.push((pos, depth));
+            }
+        }
+
+        Self { steps }
+    }
+
+    /// Number of ranges containing `value`.
+    #[must_use]
+    pub fn depth(&self, value: &T) -> usize {
+        let idx = self.steps.partition_point(|(pos, _)| pos <= value);
+
+        idx.checked_sub(1).map_or(0, |idx| self.steps[idx].1)
+    }
+
+    /// Highest number of ranges overlapping at any value.
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.steps
+            .iter()
+            .map(|(_, depth)| *depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Values covered by at least `k` ranges. Where the maximum overlap occurs is
+    /// `self.at_least(self.max_depth())`.
+    #[must_use]
+    pub fn at_least(&self, k: usize) -> RangeSet<T> {
+        let mut set = RangeSet::default();
+
+        if k == 0 {
+            set.insert(..);
+            return set;
+        }
+
+        for (i, (pos, depth)) in self.steps.iter().enumerate() {
+            if *depth < k {
+                continue;
+            }
+
+            match self.steps.get(i + 1) {
+                Some((next, _)) => set.insert_range(&(*pos..*next)),
+                None => set.insert_from(*pos),
+            }
+        }
+
+        set
+    }
+}
+
+impl<T: Countable> Coverage<T> {
+    /// Number of values covered by at least `k` ranges.
+    #[must_use]
+    pub fn len_at_least(&self, k: usize) -> T::Count {
+        self.at_least(k).len()
+    }
+}
+
+impl<T: Discrete> FromIterator<RangeInclusive<T>> for Coverage<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_depth() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let coverage = Coverage::new([0u32..=9, 5..=14, 8..=8, 15..=19, 30..=29]);
+
+        assert_eq!(
+            coverage.steps,
+            vec![(0, 1), (5, 2), (8, 3), (9, 2), (10, 1), (20, 0)]
+        );
+        assert_eq!(coverage.depth(&0), 1);
+        assert_eq!(coverage.depth(&7), 2);
+        assert_eq!(coverage.depth(&8), 3);
+        assert_eq!(coverage.depth(&14), 1);
+        assert_eq!(coverage.depth(&15), 1);
+        assert_eq!(coverage.depth(&20), 0);
+        assert_eq!(coverage.max_depth(), 3);
+
+        assert_eq!(coverage.at_least(3), RangeSet::from(vec![8..9]));
+        assert_eq!(coverage.at_least(2), RangeSet::from(vec![5..10]));
+        assert_eq!(coverage.at_least(1), RangeSet::from(vec![0..20]));
+        assert_eq!(coverage.at_least(4), RangeSet::default());
+        assert_eq!(coverage.len_at_least(0), 1 << 32);
+        assert_eq!(coverage.len_at_least(1), 20);
+        assert_eq!(coverage.len_at_least(2), 5);
+
+        let empty = Coverage::<u8>::new([]);
+
+        assert_eq!(empty.max_depth(), 0);
+        assert_eq!(empty.depth(&0), 0);
+        assert_eq!(empty.len_at_least(1), 0);
+    }
+
+    #[test]
+    fn test_up_to_max() {
+        let coverage: Coverage<u64> =
+            [0..=u64::MAX, u64::MAX - 1..=u64::MAX, 5..=5]
+                .into_iter()
+                .collect();
+
+        assert_eq!(coverage.depth(&u64::MAX), 2);
+        assert_eq!(coverage.max_depth(), 2);
+        assert_eq!(coverage.len_at_least(1), 1 << 64);
+        assert_eq!(coverage.len_at_least(2), 3);
+        assert_eq!(
+            coverage.at_least(2).iter_inclusive().collect_vec(),
+            vec![5..=5, u64::MAX - 1..=u64::MAX]
+        );
+    }
+
+    #[test]
+    fn random_depth() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let ranges = (0..rng.random_range(0..10))
+                .map(|_| {
+                    let start = rng.random_range(200..=u8::MAX);
+                    start..=rng.random_range(start..=u8::MAX)
+                })
+                .collect_vec();
+            let coverage = Coverage::new(ranges.iter().cloned());
+            let model = (0..=u8::MAX)
+                .map(|v| ranges.iter().filter(|range| range.contains(&v)).count())
+                .collect_vec();
+
+            for (v, depth) in (0..=u8::MAX).zip(&model) {
+                assert_eq!(coverage.depth(&v), *depth, "{v} {ranges:?}");
+            }
+
+            assert_eq!(
+                coverage.max_depth(),
+                model.iter().copied().max().unwrap()
+            );
+
+            for k in 1..4 {
+                let expected = model.iter().filter(|depth| **depth >= k).count();
+
+                assert_eq!(usize::from(coverage.len_at_least(k)), expected);
+            }
+        }
+    }
+}
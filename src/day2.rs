@@ -1,9 +1,13 @@
+use crate::{
+    digits::{DigitChunks, count_digits, to_string_radix},
+    rangeset::RangeSet,
+};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 use rayon::prelude::*;
-use std::ops::RangeInclusive;
+use std::{fmt, ops::RangeInclusive};
 
-type Id = u64;
+pub type Id = u128;
 
 #[aoc_generator(day2)]
 fn parse(input: &str) -> Vec<RangeInclusive<Id>> {
@@ -18,120 +22,257 @@ fn parse(input: &str) -> Vec<RangeInclusive<Id>> {
 }
 
 #[aoc(day2, part1)]
-fn part1(input: &[RangeInclusive<Id>]) -> u64 {
-    input
-        .par_iter()
-        .cloned()
-        .flat_map(|range| range)
-        .filter(|id| is_invalid_part1(*id))
-        .sum::<u64>()
+fn part1(input: &[RangeInclusive<Id>]) -> Id {
+    sum_invalid(input, 10, Rule::Twice, Semantics::Set)
 }
 
 #[aoc(day2, part2)]
-fn part2(input: &[RangeInclusive<Id>]) -> u64 {
-    input
-        .par_iter()
-        .cloned()
-        .flat_map(|range| range)
-        .filter(|id| is_invalid_part2(*id))
-        .sum::<u64>()
+fn part2(input: &[RangeInclusive<Id>]) -> Id {
+    sum_invalid(input, 10, Rule::AtLeastTwice, Semantics::Set)
 }
 
-fn is_invalid_part1(id: Id) -> bool {
-    RevDigitsIter::new(id, 2)
-        .and_then(|mut iter| Some(iter.next()? == iter.next()?))
-        .unwrap_or(false)
+/// How IDs covered by more than one input range are counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Semantics {
+    /// Each range is scanned on its own, so IDs are counted once per range.
+    Multiset,
+    /// Ranges are merged first, so IDs are counted once.
+    Set,
 }
 
-fn is_invalid_part2(id: Id) -> bool {
-    [1u32, 2, 3, 5]
-        .into_iter()
-        .filter_map(|factor| {
-            let digits = count_digits(id);
-
-            if digits == 0 {
-                return None;
-            }
-
-            let chunks = digits / factor;
+/// Sums the IDs in `input` that are invalid under `rule` when written in `radix`.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn sum_invalid(
+    input: &[RangeInclusive<Id>],
+    radix: u32,
+    rule: Rule,
+    semantics: Semantics,
+) -> Id {
+    let ranges = match semantics {
+        Semantics::Multiset => input.to_vec(),
+        Semantics::Set => normalize(input),
+    };
+
+    ranges
+        .into_par_iter()
+        .flat_map(|range| range)
+        .filter(|id| repetitions(*id, radix, rule).is_some())
+        .sum::<Id>()
+}
 
-            if chunks == 1 {
-                return None;
-            }
+/// Merges overlapping and adjacent ranges, sorting them.
+#[must_use]
+pub fn normalize(input: &[RangeInclusive<Id>]) -> Vec<RangeInclusive<Id>> {
+    input
+        .iter()
+        .cloned()
+        .collect::<RangeSet<_>>()
+        .iter_inclusive()
+        .collect()
+}
 
-            Some(digits / factor)
-        })
-        .filter_map(|chunks| RevDigitsIter::new(id, chunks))
-        .any(|iter| iter.tuple_windows().all(|(a, b)| a == b))
+/// Which repetitions make an ID invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Some sequence of digits repeated exactly twice (part 1).
+    Twice,
+    /// Some sequence of digits repeated at least twice (part 2).
+    AtLeastTwice,
 }
 
-struct RevDigitsIter {
-    n: u64,
-    digits: u32,
-    chunks: u32,
-    curr: u32,
+/// Whether `id`, written in `radix`, is some sequence of digits repeated twice.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn is_invalid_part1(id: Id, radix: u32) -> bool {
+    repetitions(id, radix, Rule::Twice).is_some()
 }
 
-impl RevDigitsIter {
-    const fn new(n: u64, chunks: u32) -> Option<Self> {
-        let digits = count_digits(n);
+/// Whether `id`, written in `radix`, is some sequence of digits repeated at least
+/// twice.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn is_invalid_part2(id: Id, radix: u32) -> bool {
+    repetitions(id, radix, Rule::AtLeastTwice).is_some()
+}
 
-        if !digits.is_multiple_of(chunks) {
-            return None;
+/// How many times the shortest repeating unit allowed by `rule` occurs in `id`,
+/// written in `radix`. Returns `None` if `id` is valid.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn repetitions(id: Id, radix: u32, rule: Rule) -> Option<u32> {
+    match rule {
+        Rule::Twice => DigitChunks::new(id, radix, 2)
+            .and_then(|mut iter| (iter.next()? == iter.next()?).then_some(2)),
+        Rule::AtLeastTwice => {
+            let digits = count_digits(id, radix);
+
+            // Every proper divisor of the digit count is a candidate unit length,
+            // i.e. the ID is split into at least two chunks. Trying the most
+            // chunks first finds the shortest unit.
+            (2..=digits)
+                .rev()
+                .filter(|chunks| digits.is_multiple_of(*chunks))
+                .find(|chunks| {
+                    DigitChunks::new(id, radix, *chunks)
+                        .is_some_and(|mut iter| iter.all_equal())
+                })
         }
-
-        Some(Self {
-            n,
-            digits,
-            chunks,
-            curr: 0,
-        })
     }
 }
 
-impl Iterator for RevDigitsIter {
-    type Item = u64;
+/// An invalid ID, made of `unit` repeated `repetitions` times.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidId {
+    pub id: Id,
+    pub unit: String,
+    pub repetitions: u32,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.n == 0 {
-            if self.curr == 0 {
-                self.curr += 1;
+impl InvalidId {
+    fn new(id: Id, radix: u32, rule: Rule) -> Option<Self> {
+        let repetitions = repetitions(id, radix, rule)?;
+        let mut unit = to_string_radix(id, radix);
 
-                return Some(0);
-            }
+        unit.truncate(unit.len() / repetitions as usize);
 
-            return None;
-        }
+        Some(Self {
+            id,
+            unit,
+            repetitions,
+        })
+    }
+}
 
-        let mut chunk = 0;
+/// The invalid IDs found in a single input range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeReport {
+    pub range: RangeInclusive<Id>,
+    pub invalid: Vec<InvalidId>,
+}
 
-        for _ in 0..self.digits / self.chunks {
-            chunk *= 10;
-            chunk += self.n % 10;
+/// Every invalid ID of the input, grouped by range in input order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub radix: u32,
+    pub rule: Rule,
+    pub ranges: Vec<RangeReport>,
+}
 
-            self.n /= 10;
+impl Report {
+    /// Lists the invalid IDs of each range in `input`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not between 2 and 36.
+    #[must_use]
+    pub fn new(input: &[RangeInclusive<Id>], radix: u32, rule: Rule) -> Self {
+        let ranges = input
+            .par_iter()
+            .map(|range| RangeReport {
+                range: range.clone(),
+                invalid: range
+                    .clone()
+                    .filter_map(|id| InvalidId::new(id, radix, rule))
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            radix,
+            rule,
+            ranges,
         }
-
-        self.curr += 1;
-
-        Some(chunk)
     }
-}
 
-const fn count_digits(mut n: u64) -> u32 {
-    if n == 0 {
-        return 1;
+    /// Sum of all invalid IDs, as returned by the solvers.
+    #[must_use]
+    pub fn sum(&self) -> Id {
+        self.ranges
+            .iter()
+            .flat_map(|range| &range.invalid)
+            .map(|invalid| invalid.id)
+            .sum()
     }
 
-    let mut count = 0;
-
-    loop {
-        n /= 10;
-        count += 1;
+    /// Renders the report as JSON, one range per line. IDs are written as strings
+    /// in the report's radix, since they can exceed what JSON numbers represent
+    /// exactly.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let rule = match self.rule {
+            Rule::Twice => "twice",
+            Rule::AtLeastTwice => "at_least_twice",
+        };
+
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|range| {
+                let invalid = range
+                    .invalid
+                    .iter()
+                    .map(|invalid| {
+                        format!(
+                            r#"{{"id":"{}","unit":"{}","repetitions":{}}}"#,
+                            to_string_radix(invalid.id, self.radix),
+                            invalid.unit,
+                            invalid.repetitions,
+                        )
+                    })
+                    .join(",");
+
+                format!(
+                    r#"  {{"from":"{}","to":"{}","invalid":[{invalid}]}}"#,
+                    to_string_radix(*range.range.start(), self.radix),
+                    to_string_radix(*range.range.end(), self.radix),
+                )
+            })
+            .join(",\n");
+
+        format!(
+            "{{\"radix\":{},\"rule\":\"{rule}\",\"sum\":\"{}\",\"ranges\":[\n{ranges}\n]}}\n",
+            self.radix,
+            to_string_radix(self.sum(), self.radix),
+        )
+    }
+}
 
-        if n == 0 {
-            break count;
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for range in &self.ranges {
+            writeln!(
+                f,
+                "{}-{}: {} invalid",
+                to_string_radix(*range.range.start(), self.radix),
+                to_string_radix(*range.range.end(), self.radix),
+                range.invalid.len(),
+            )?;
+
+            for invalid in &range.invalid {
+                writeln!(
+                    f,
+                    "  {} = \"{}\" × {}",
+                    to_string_radix(invalid.id, self.radix),
+                    invalid.unit,
+                    invalid.repetitions,
+                )?;
+            }
         }
+
+        writeln!(f, "sum: {}", to_string_radix(self.sum(), self.radix))
     }
 }
 
@@ -155,37 +296,147 @@ mod tests {
     }
 
     #[test]
-    fn rev_digits_iterator() {
-        macro_rules! run {
-            ($n:expr, $chunks:expr) => {
-                RevDigitsIter::new($n, $chunks).unwrap().collect::<Vec<_>>()
-            };
-        }
+    fn is_invalid_part2_() {
+        assert!(!is_invalid_part2(1, 10));
+        assert!(!is_invalid_part2(11_12, 10));
+        assert!(is_invalid_part2(10_10, 10));
+        assert!(is_invalid_part2(1_1, 10));
+        assert!(is_invalid_part2(1_1_1_1_1_1_1, 10));
+        assert!(is_invalid_part2(1_188_511_885, 10));
+        assert!(is_invalid_part2(12_12_12_12_12, 10));
+        assert!(is_invalid_part2(123_123_123, 10));
+        assert!(is_invalid_part2(1234_1234, 10));
+        assert!(is_invalid_part2(21_21_21_21_21, 10));
+        assert!(is_invalid_part2(3859_3859, 10));
+        assert!(is_invalid_part2(446_446, 10));
+        assert!(is_invalid_part2(565_656, 10));
+        assert!(!is_invalid_part2(6_758_902_779, 10));
+    }
 
-        assert_eq!(vec![0], run!(0, 1));
-        assert_eq!(vec![21], run!(12, 1));
-        assert_eq!(vec![321], run!(123, 1));
-        assert_eq!(vec![2, 1], run!(12, 2));
-        assert_eq!(vec![43, 21], run!(1_234, 2));
-        assert_eq!(vec![654, 321], run!(123_456, 2));
+    #[test]
+    fn is_invalid_part2_long_ids() {
+        assert!(is_invalid_part2(12_345_671_234_567, 10));
+        assert!(is_invalid_part2(123_456_712_345_671_234_567, 10));
+        assert!(is_invalid_part2(
+            1_234_567_890_123_456_789 * 10_000_000_000_000_000_001,
+            10
+        ));
+        assert!(is_invalid_part2(
+            11_111_111_111_111_111_111_111_111_111_111_111_111,
+            10
+        ));
+        assert!(!is_invalid_part2(12_345_671_234_568, 10));
+        assert!(!is_invalid_part2(123_456_712_345_671_234_568, 10));
+        assert!(!is_invalid_part2(u128::MAX, 10));
     }
 
     #[test]
-    fn is_invalid_part2_() {
-        assert!(!is_invalid_part2(1));
-        assert!(!is_invalid_part2(11_12));
-        assert!(is_invalid_part2(10_10));
-        assert!(is_invalid_part2(1_1));
-        assert!(is_invalid_part2(1_1_1_1_1_1_1));
-        assert!(is_invalid_part2(1_188_511_885));
-        assert!(is_invalid_part2(12_12_12_12_12));
-        assert!(is_invalid_part2(123_123_123));
-        assert!(is_invalid_part2(1234_1234));
-        assert!(is_invalid_part2(21_21_21_21_21));
-        assert!(is_invalid_part2(3859_3859));
-        assert!(is_invalid_part2(446_446));
-        assert!(is_invalid_part2(565_656));
-        assert!(!is_invalid_part2(6_758_902_779));
+    fn other_radixes() {
+        assert!(is_invalid_part1(0xabab, 16));
+        assert!(!is_invalid_part1(0xabab, 10));
+        assert!(is_invalid_part1(0b1010, 2));
+        assert!(!is_invalid_part1(0b1010, 10));
+        assert!(is_invalid_part2(0xab_ab_ab, 16));
+        assert!(is_invalid_part2(0b101_101_101, 2));
+        assert!(!is_invalid_part2(0b101_101_100, 2));
+        assert!(is_invalid_part2(u128::MAX, 2));
+        assert!(is_invalid_part2(u128::MAX, 16));
+        assert!(!is_invalid_part2(u128::MAX, 10));
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        let input = parse("11-22,15-33,95-99,96-115,200-100");
+
+        assert_eq!(normalize(&input), vec![11..=33, 95..=115]);
+        assert_eq!(
+            sum_invalid(&input, 10, Rule::Twice, Semantics::Set),
+            11 + 22 + 33 + 99
+        );
+        assert_eq!(
+            sum_invalid(&input, 10, Rule::Twice, Semantics::Multiset),
+            11 + 22 + 22 + 33 + 99 + 99
+        );
+        assert_eq!(
+            sum_invalid(&input, 10, Rule::AtLeastTwice, Semantics::Set),
+            11 + 22 + 33 + 99 + 111
+        );
+        assert_eq!(part1(&input), 11 + 22 + 33 + 99);
+    }
+
+    #[test]
+    fn report() {
+        let input = parse(EXAMPLE);
+        let report = Report::new(&input, 10, Rule::AtLeastTwice);
+
+        assert_eq!(report.ranges.len(), input.len());
+        assert_eq!(report.sum(), part2(&input));
+        assert_eq!(
+            report.ranges[9].invalid,
+            vec![InvalidId {
+                id: 824_824_824,
+                unit: "824".into(),
+                repetitions: 3,
+            }]
+        );
+        assert_eq!(Report::new(&input, 10, Rule::Twice).sum(), part1(&input));
+    }
+
+    #[test]
+    fn report_text() {
+        let input = parse("95-115,222220-222224");
+
+        assert_eq!(
+            Report::new(&input, 10, Rule::AtLeastTwice).to_string(),
+            r#"95-115: 2 invalid
+  99 = "9" × 2
+  111 = "1" × 3
+222220-222224: 1 invalid
+  222222 = "2" × 6
+sum: 222432
+"#
+        );
+
+        assert_eq!(
+            Report::new(&input, 10, Rule::Twice).to_string(),
+            r#"95-115: 1 invalid
+  99 = "9" × 2
+222220-222224: 1 invalid
+  222222 = "222" × 2
+sum: 222321
+"#
+        );
+    }
+
+    #[test]
+    fn report_json() {
+        let input = parse("95-115,1-9,170-171");
+
+        assert_eq!(
+            Report::new(&input, 16, Rule::AtLeastTwice).to_json(),
+            r#"{"radix":16,"rule":"at_least_twice","sum":"110","ranges":[
+  {"from":"5f","to":"73","invalid":[{"id":"66","unit":"6","repetitions":2}]},
+  {"from":"1","to":"9","invalid":[]},
+  {"from":"aa","to":"ab","invalid":[{"id":"aa","unit":"a","repetitions":2}]}
+]}
+"#
+        );
+    }
+
+    #[test]
+    fn long_ranges() {
+        let input = parse(
+            "12345678901234567885-12345678901234567895,99999999999999999990-100000000000000000010",
+        );
+
+        assert_eq!(
+            part1(&input),
+            12_345_678_901_234_567_890 + 99_999_999_999_999_999_999
+        );
+        assert_eq!(
+            part2(&input),
+            12_345_678_901_234_567_890 + 99_999_999_999_999_999_999
+        );
     }
 
     #[test]
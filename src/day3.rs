@@ -1,68 +1,451 @@
+use crate::digits::parse_digit;
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
-use std::cmp::Ordering;
+use std::fmt;
 
 #[aoc_generator(day3)]
 fn parse(input: &str) -> Vec<Vec<u8>> {
-    let mut buf = [0u8; 4];
+    parse_banks(input, ParseOptions::default()).unwrap().banks
+}
 
-    input
-        .trim_ascii()
+/// How [`parse_banks`] treats banks whose length differs from the first one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lengths {
+    /// Any length is accepted.
+    #[default]
+    Any,
+    /// Any length is accepted, but differing banks are reported in
+    /// [`Banks::warnings`].
+    Warn,
+    /// Differing banks are an error.
+    Equal,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Whether batteries of rank 0 are accepted, on top of ranks 1 to 9.
+    pub allow_zero: bool,
+    pub lengths: Lengths,
+}
+
+/// A bank whose length differs from the first one. Indices are zero-based.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub bank: usize,
+    pub len: usize,
+    pub expected: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Banks {
+    pub banks: Vec<Vec<u8>>,
+    pub warnings: Vec<LengthMismatch>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A cell which isn't a battery of an accepted rank. Indices are zero-based.
+    InvalidCell {
+        bank: usize,
+        column: usize,
+        byte: u8,
+    },
+    /// A blank line between banks. Index is zero-based.
+    EmptyBank {
+        bank: usize,
+    },
+    LengthMismatch(LengthMismatch),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCell { bank, column, byte } => write!(
+                f,
+                "invalid battery '{}' in bank {}, column {}",
+                byte.escape_ascii(),
+                bank + 1,
+                column + 1
+            ),
+            Self::EmptyBank { bank } => {
+                write!(f, "bank {} is empty", bank + 1)
+            }
+            Self::LengthMismatch(LengthMismatch {
+                bank,
+                len,
+                expected,
+            }) => {
+                write!(
+                    f,
+                    "bank {} has {len} batteries, expected {expected}",
+                    bank + 1
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses one bank per line, each battery being a single digit. Whitespace around a
+/// bank is ignored, but columns are still counted from the start of its line.
+///
+/// # Errors
+///
+/// Fails on the first cell that isn't a battery of an accepted rank, on a blank line
+/// between banks, or on the first bank of differing length with [`Lengths::Equal`].
+pub fn parse_banks(
+    input: &str,
+    options: ParseOptions,
+) -> Result<Banks, ParseError> {
+    let min_rank = u8::from(!options.allow_zero);
+    let mut banks = Vec::new();
+    let mut warnings = Vec::new();
+
+    let lines = input
+        .trim_ascii_end()
         .lines()
-        .map(|line| {
-            line.trim_ascii()
-                .chars()
-                .map(|c| c.encode_utf8(&mut buf).parse().unwrap())
-                .collect_vec()
-        })
-        .collect_vec()
+        .skip_while(|line| line.trim_ascii().is_empty());
+
+    for (bank, line) in lines.enumerate() {
+        let indent = line.len() - line.trim_ascii_start().len();
+        let cells = line
+            .trim_ascii()
+            .bytes()
+            .enumerate()
+            .map(|(i, byte)| {
+                let column = indent + i;
+
+                parse_digit(byte, 10)
+                    .filter(|rank| *rank >= min_rank)
+                    .ok_or(ParseError::InvalidCell { bank, column, byte })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if cells.is_empty() {
+            return Err(ParseError::EmptyBank { bank });
+        }
+
+        if let Some(first) = banks.first().map(Vec::len)
+            && first != cells.len()
+        {
+            let mismatch = LengthMismatch {
+                bank,
+                len: cells.len(),
+                expected: first,
+            };
+
+            match options.lengths {
+                Lengths::Any => {}
+                Lengths::Warn => warnings.push(mismatch),
+                Lengths::Equal => {
+                    return Err(ParseError::LengthMismatch(mismatch));
+                }
+            }
+        }
+
+        banks.push(cells);
+    }
+
+    Ok(Banks { banks, warnings })
 }
 
 #[aoc(day3, part1)]
 fn part1(input: &[Vec<u8>]) -> u64 {
-    input.iter().map(|bank| largest_joltage(bank, 2)).sum()
+    input
+        .iter()
+        .map(|bank| largest_joltage(bank, 2).to_u64().unwrap())
+        .sum()
 }
 
 #[aoc(day3, part2)]
 fn part2(input: &[Vec<u8>]) -> u64 {
-    input.iter().map(|bank| largest_joltage(bank, 12)).sum()
+    input
+        .iter()
+        .map(|bank| largest_joltage(bank, 12).to_u64().unwrap())
+        .sum()
 }
 
-const fn compare_batt(batt1: u8, batt2: u8) -> Ordering {
-    if batt2 > batt1 {
-        Ordering::Less
-    } else {
-        Ordering::Greater
+/// The batteries switched on in a bank, and the joltage they produce.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+    /// Indices of the batteries switched on, in increasing order.
+    pub indices: Vec<usize>,
+    /// Decimal digits of the joltage, one per battery switched on.
+    pub joltage: String,
+}
+
+impl Selection {
+    fn new(bank: &[u8], indices: Vec<usize>) -> Self {
+        let joltage = indices
+            .iter()
+            .map(|idx| char::from(b'0' + bank[*idx]))
+            .collect();
+
+        Self { indices, joltage }
+    }
+
+    /// The joltage as a number, if it fits in a `u64`.
+    #[must_use]
+    pub fn to_u64(&self) -> Option<u64> {
+        self.joltage.parse().ok()
+    }
+
+    /// Renders `bank` with a `^` marker under each battery switched on.
+    #[must_use]
+    pub fn render(&self, bank: &[u8]) -> String {
+        let mut markers = vec![' '; bank.len()];
+
+        for idx in &self.indices {
+            markers[*idx] = '^';
+        }
+
+        format!(
+            "{}\n{}",
+            bank.iter().map(|batt| char::from(b'0' + batt)).join(""),
+            markers.into_iter().collect::<String>().trim_end()
+        )
     }
 }
 
-fn largest_joltage(bank: &[u8], count: usize) -> u64 {
-    let mut joltage = 0;
-    let mut batt_idx = 0;
+/// Switches on the `count` batteries producing the largest joltage, preferring the
+/// leftmost battery among equally good ones.
+///
+/// # Panics
+///
+/// Panics if the bank has fewer than `count` batteries.
+#[must_use]
+pub fn largest_joltage(bank: &[u8], count: usize) -> Selection {
+    // Monotonic stack: a battery is dropped as soon as a stronger one follows it, as
+    // long as there are enough batteries left to switch `count` of them on.
+    let mut drops = bank.len() - count;
+    let mut stack = Vec::<usize>::with_capacity(bank.len());
 
-    for offset in 1..=count {
-        let up_to_idx = bank.len() - count + offset;
+    for (idx, batt) in bank.iter().enumerate() {
+        while drops > 0 && stack.last().is_some_and(|top| bank[*top] < *batt) {
+            stack.pop();
+            drops -= 1;
+        }
 
-        batt_idx = bank[..up_to_idx]
-            .iter()
-            .enumerate()
-            .skip(batt_idx)
-            .max_by(|(_, batt1), (_, batt2)| compare_batt(**batt1, **batt2))
-            .map(|x| x.0)
-            .unwrap();
+        stack.push(idx);
+    }
 
-        joltage *= 10;
-        joltage += u64::from(bank[batt_idx]);
+    stack.truncate(count);
 
-        batt_idx += 1;
+    Selection::new(bank, stack)
+}
+
+/// Switches on the `count` batteries producing the smallest joltage whose first digit
+/// isn't zero. Returns `None` if there is no such selection.
+#[must_use]
+pub fn smallest_joltage(bank: &[u8], count: usize) -> Option<Selection> {
+    Subsequences::new(bank).smallest(count)
+}
+
+/// Switches on `count` batteries producing the `n`-th largest distinct joltage, `0`
+/// being the largest. Returns `None` if there are not enough distinct joltages.
+#[must_use]
+pub fn nth_largest_joltage(
+    bank: &[u8],
+    count: usize,
+    n: u128,
+) -> Option<Selection> {
+    Subsequences::new(bank).nth_largest(count, n)
+}
+
+/// Number of ways to switch on `count` batteries producing the largest joltage,
+/// saturating at `u128::MAX`.
+///
+/// # Panics
+///
+/// Panics if the bank has fewer than `count` batteries.
+#[must_use]
+pub fn count_largest_selections(bank: &[u8], count: usize) -> u128 {
+    let largest = largest_joltage(bank, count);
+    let pattern = largest.indices.iter().map(|idx| bank[*idx]).collect_vec();
+
+    Subsequences::new(bank).count_embeddings(&pattern)
+}
+
+/// Dynamic programming over the subsequences of a bank, built on a table of the next
+/// battery of each rank from every position.
+///
+/// Selections are always made of the leftmost batteries that produce a joltage.
+pub struct Subsequences<'a> {
+    bank: &'a [u8],
+    /// `next[idx][rank]` is the index of the first battery of `rank` at or after
+    /// `idx`, or the bank's length if there is none.
+    next: Vec<[usize; 10]>,
+}
+
+impl<'a> Subsequences<'a> {
+    /// # Panics
+    ///
+    /// Panics if a battery has a rank above 9.
+    #[must_use]
+    pub fn new(bank: &'a [u8]) -> Self {
+        let mut next = vec![[bank.len(); 10]; bank.len() + 1];
+
+        for (idx, batt) in bank.iter().enumerate().rev() {
+            next[idx] = next[idx + 1];
+            next[idx][usize::from(*batt)] = idx;
+        }
+
+        Self { bank, next }
+    }
+
+    /// Same as [`largest_joltage`], in O(n + count·10) once the table is built.
+    #[must_use]
+    pub fn largest(&self, count: usize) -> Option<Selection> {
+        self.greedy(count, |_| (0..=9).rev())
+    }
+
+    /// Same as [`smallest_joltage`].
+    #[must_use]
+    pub fn smallest(&self, count: usize) -> Option<Selection> {
+        self.greedy(count, |step| u8::from(step == 0)..=9)
     }
 
-    joltage
+    /// Same as [`nth_largest_joltage`]. Takes O(n·count) time and memory.
+    #[must_use]
+    pub fn nth_largest(&self, count: usize, mut n: u128) -> Option<Selection> {
+        let distinct = self.count_distinct(count);
+
+        if n >= distinct[0][count] {
+            return None;
+        }
+
+        let mut indices = Vec::with_capacity(count);
+        let mut pos = 0;
+
+        for remaining in (1..=count).rev() {
+            let idx = (0..=9).rev().find_map(|rank| {
+                let idx = self.next[pos][rank];
+
+                if idx == self.bank.len() {
+                    return None;
+                }
+
+                let below = distinct[idx + 1][remaining - 1];
+
+                if n < below {
+                    Some(idx)
+                } else {
+                    n -= below;
+                    None
+                }
+            })?;
+
+            indices.push(idx);
+            pos = idx + 1;
+        }
+
+        Some(Selection::new(self.bank, indices))
+    }
+
+    /// Number of ways `pattern` appears as a subsequence of the bank, saturating at
+    /// `u128::MAX`.
+    #[must_use]
+    pub fn count_embeddings(&self, pattern: &[u8]) -> u128 {
+        // `ways[len]` counts the embeddings of `pattern[..len]` seen so far.
+        let mut ways = vec![0u128; pattern.len() + 1];
+        ways[0] = 1;
+
+        for batt in self.bank {
+            for (len, rank) in pattern.iter().enumerate().rev() {
+                if rank == batt {
+                    ways[len + 1] = ways[len + 1].saturating_add(ways[len]);
+                }
+            }
+        }
+
+        ways[pattern.len()]
+    }
+
+    /// Builds a selection one battery at a time, picking the first rank of
+    /// `ranks(step)` that still leaves enough batteries for the following steps.
+    fn greedy<I: Iterator<Item = u8>>(
+        &self,
+        count: usize,
+        ranks: impl Fn(usize) -> I,
+    ) -> Option<Selection> {
+        let mut indices = Vec::with_capacity(count);
+        let mut pos = 0;
+
+        for step in 0..count {
+            let remaining = count - step;
+            let idx = ranks(step)
+                .map(|rank| self.next[pos][usize::from(rank)])
+                .find(|idx| self.bank.len() - idx >= remaining)?;
+
+            indices.push(idx);
+            pos = idx + 1;
+        }
+
+        Some(Selection::new(self.bank, indices))
+    }
+
+    /// `distinct[idx][len]` is the number of distinct joltages of `len` batteries
+    /// taken from `bank[idx..]`, saturating at `u128::MAX`.
+    fn count_distinct(&self, count: usize) -> Vec<Vec<u128>> {
+        let mut distinct = vec![vec![0u128; count + 1]; self.bank.len() + 1];
+        distinct[self.bank.len()][0] = 1;
+
+        for idx in (0..self.bank.len()).rev() {
+            distinct[idx][0] = 1;
+
+            for len in 1..=count {
+                distinct[idx][len] = self.next[idx]
+                    .iter()
+                    .filter(|next| **next < self.bank.len())
+                    .fold(0u128, |acc, next| {
+                        acc.saturating_add(distinct[next + 1][len - 1])
+                    });
+            }
+        }
+
+        distinct
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::prelude::*;
+    use std::cmp::Ordering;
+
+    // The original O(n·k) implementation, kept as a reference.
+    const fn compare_batt(batt1: u8, batt2: u8) -> Ordering {
+        if batt2 > batt1 {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+
+    fn largest_joltage_rescan(bank: &[u8], count: usize) -> u64 {
+        let mut joltage = 0;
+        let mut batt_idx = 0;
+
+        for offset in 1..=count {
+            let up_to_idx = bank.len() - count + offset;
+
+            batt_idx = bank[..up_to_idx]
+                .iter()
+                .enumerate()
+                .skip(batt_idx)
+                .max_by(|(_, batt1), (_, batt2)| compare_batt(**batt1, **batt2))
+                .map(|x| x.0)
+                .unwrap();
+
+            joltage *= 10;
+            joltage += u64::from(bank[batt_idx]);
+
+            batt_idx += 1;
+        }
+
+        joltage
+    }
 
     const EXAMPLE: &str = "987654321111111
 811111111111119
@@ -94,4 +477,261 @@ mod tests {
             173_300_819_005_913
         );
     }
+
+    #[test]
+    fn largest_joltage_matches_rescan() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..10_000 {
+            let len = rng.random_range(1..60);
+            let bank = (0..len).map(|_| rng.random_range(1..=9)).collect_vec();
+            let count = rng.random_range(1..=len.min(19));
+
+            let selection = largest_joltage(&bank, count);
+
+            assert_eq!(
+                selection.to_u64(),
+                Some(largest_joltage_rescan(&bank, count)),
+                "{bank:?}, {count}"
+            );
+            assert!(selection.indices.iter().tuple_windows().all(|(a, b)| a < b));
+            assert_eq!(selection.indices.len(), count);
+            assert_eq!(
+                selection.joltage,
+                selection
+                    .indices
+                    .iter()
+                    .map(|idx| char::from(b'0' + bank[*idx]))
+                    .collect::<String>()
+            );
+        }
+    }
+
+    #[test]
+    fn largest_joltage_long_bank() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let mut bank = (0..4_000_000)
+            .map(|_| rng.random_range(1..=8))
+            .collect_vec();
+
+        for idx in (0..19).map(|i| 1_000_000 + i * 100_000) {
+            bank[idx] = 9;
+        }
+
+        assert_eq!(
+            largest_joltage(&bank, 19).to_u64(),
+            Some(9_999_999_999_999_999_999)
+        );
+
+        let selection = largest_joltage(&bank, 1_000_000);
+
+        assert_eq!(selection.indices.len(), 1_000_000);
+        assert!(selection.joltage.starts_with(&"9".repeat(19)));
+        assert_eq!(selection.to_u64(), None);
+    }
+
+    #[test]
+    fn selection() {
+        let bank = &parse(EXAMPLE)[3];
+        let selection = largest_joltage(bank, 12);
+
+        assert_eq!(selection.joltage, "888911112111");
+        assert_eq!(
+            selection.indices,
+            vec![0, 2, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(selection.render(bank), "818181911112111\n^ ^ ^ ^^^^^^^^^");
+        assert_eq!(
+            largest_joltage(bank, 2).render(bank),
+            "818181911112111\n      ^    ^"
+        );
+    }
+
+    #[test]
+    fn subsequence_queries_match_brute_force() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..2_000 {
+            let len = rng.random_range(1..=10);
+            let bank = (0..len).map(|_| rng.random_range(0..=3)).collect_vec();
+            let count = rng.random_range(0..=len);
+
+            let joltages = (0..len)
+                .combinations(count)
+                .map(|indices| Selection::new(&bank, indices).joltage)
+                .collect_vec();
+            let distinct = joltages.iter().sorted().rev().dedup().collect_vec();
+
+            assert_eq!(
+                smallest_joltage(&bank, count).map(|s| s.joltage),
+                joltages
+                    .iter()
+                    .filter(|joltage| !joltage.starts_with('0'))
+                    .min()
+                    .cloned(),
+                "{bank:?}, {count}"
+            );
+
+            for (n, joltage) in distinct.iter().enumerate() {
+                assert_eq!(
+                    &nth_largest_joltage(&bank, count, n as u128)
+                        .unwrap()
+                        .joltage,
+                    *joltage,
+                    "{bank:?}, {count}, {n}"
+                );
+            }
+            assert_eq!(
+                nth_largest_joltage(&bank, count, distinct.len() as u128),
+                None
+            );
+
+            assert_eq!(
+                count_largest_selections(&bank, count),
+                joltages.iter().filter(|j| j == &distinct[0]).count() as u128,
+                "{bank:?}, {count}"
+            );
+
+            assert_eq!(
+                Subsequences::new(&bank).largest(count),
+                Some(largest_joltage(&bank, count))
+            );
+        }
+    }
+
+    #[test]
+    fn subsequence_queries_example() {
+        let banks = parse(EXAMPLE);
+
+        assert_eq!(smallest_joltage(&banks[0], 3).unwrap().joltage, "111");
+        assert_eq!(smallest_joltage(&banks[1], 2).unwrap().joltage, "11");
+        assert_eq!(smallest_joltage(&[0, 0, 3, 0], 2).unwrap().joltage, "30");
+        assert_eq!(smallest_joltage(&[0, 0, 3, 0], 3), None);
+        assert_eq!(smallest_joltage(&[1, 2], 3), None);
+
+        assert_eq!(nth_largest_joltage(&banks[0], 2, 0).unwrap().joltage, "98");
+        assert_eq!(nth_largest_joltage(&banks[0], 2, 1).unwrap().joltage, "97");
+        assert_eq!(nth_largest_joltage(&banks[0], 2, 8).unwrap().joltage, "87");
+        assert_eq!(nth_largest_joltage(&[1, 2, 3], 0, 0).unwrap().joltage, "");
+        assert_eq!(nth_largest_joltage(&[1, 2, 3], 0, 5), None);
+        assert_eq!(nth_largest_joltage(&[1, 2, 3], 4, 0), None);
+
+        assert_eq!(count_largest_selections(&banks[0], 2), 1);
+        assert_eq!(count_largest_selections(&banks[1], 2), 1);
+        assert_eq!(count_largest_selections(&banks[1], 3), 13);
+        assert_eq!(count_largest_selections(&[9; 200], 100), u128::MAX);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let options = ParseOptions::default();
+
+        assert_eq!(
+            parse_banks("123\n45x\n789", options),
+            Err(ParseError::InvalidCell {
+                bank: 1,
+                column: 2,
+                byte: b'x'
+            })
+        );
+        assert_eq!(
+            parse_banks("123\n405\n789", options)
+                .unwrap_err()
+                .to_string(),
+            "invalid battery '0' in bank 2, column 2"
+        );
+        assert_eq!(
+            parse_banks("123\n  45x\n789", options),
+            Err(ParseError::InvalidCell {
+                bank: 1,
+                column: 4,
+                byte: b'x'
+            })
+        );
+        assert_eq!(
+            parse_banks("\n  12\n\t 34 \n56", options).unwrap().banks,
+            vec![vec![1, 2], vec![3, 4], vec![5, 6]]
+        );
+        assert_eq!(
+            parse_banks("12\n\n34", options),
+            Err(ParseError::EmptyBank { bank: 1 })
+        );
+        assert_eq!(
+            parse_banks("12\n \t\n34\n", options)
+                .unwrap_err()
+                .to_string(),
+            "bank 2 is empty"
+        );
+        assert_eq!(
+            parse_banks(" 1x", options).unwrap_err().to_string(),
+            "invalid battery 'x' in bank 1, column 3"
+        );
+        assert_eq!(
+            parse_banks("12é", options).unwrap_err().to_string(),
+            "invalid battery '\\xc3' in bank 1, column 3"
+        );
+        assert_eq!(
+            parse_banks(
+                "123\n405\n789",
+                ParseOptions {
+                    allow_zero: true,
+                    ..options
+                }
+            )
+            .unwrap()
+            .banks,
+            vec![vec![1, 2, 3], vec![4, 0, 5], vec![7, 8, 9]]
+        );
+    }
+
+    #[test]
+    fn parse_lengths() {
+        let input = "123\n45\n6789\r\n123\n";
+        let mismatches = vec![
+            LengthMismatch {
+                bank: 1,
+                len: 2,
+                expected: 3,
+            },
+            LengthMismatch {
+                bank: 2,
+                len: 4,
+                expected: 3,
+            },
+        ];
+
+        let any = parse_banks(input, ParseOptions::default()).unwrap();
+
+        assert_eq!(
+            any.banks,
+            vec![vec![1, 2, 3], vec![4, 5], vec![6, 7, 8, 9], vec![1, 2, 3]]
+        );
+        assert!(any.warnings.is_empty());
+
+        let warn = parse_banks(
+            input,
+            ParseOptions {
+                lengths: Lengths::Warn,
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(warn.banks, any.banks);
+        assert_eq!(warn.warnings, mismatches);
+
+        let equal = parse_banks(
+            input,
+            ParseOptions {
+                lengths: Lengths::Equal,
+                ..ParseOptions::default()
+            },
+        );
+
+        assert_eq!(equal, Err(ParseError::LengthMismatch(mismatches[0])));
+        assert_eq!(
+            equal.unwrap_err().to_string(),
+            "bank 2 has 2 batteries, expected 3"
+        );
+    }
 }
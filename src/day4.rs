@@ -1,7 +1,10 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 use ndarray::prelude::*;
+use std::{borrow::Cow, fs, io, path::Path};
 
-type Grid = Array2<u8>;
+/// Rolls are `1`, empty cells `0`.
+pub type Grid = Array2<u8>;
 
 #[aoc_generator(day4)]
 fn parse(input: &str) -> Grid {
@@ -23,7 +26,69 @@ fn parse(input: &str) -> Grid {
 
 #[aoc(day4, part1)]
 fn part1(input: &Grid) -> usize {
-    input
+    accessible(input, Boundary::Empty)
+}
+
+#[aoc(day4, part2)]
+fn part2(input: &Grid) -> usize {
+    removable(input, Boundary::Empty)
+}
+
+/// What lies beyond the edges of the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Empty cells.
+    #[default]
+    Empty,
+    /// Rolls, which are never removed.
+    Occupied,
+    /// The grid wraps around, e.g. the cell left of the first column is on the last
+    /// column.
+    Toroidal,
+    /// The grid is mirrored at its edges, e.g. the cell left of the first column is
+    /// the first column itself.
+    Reflect,
+}
+
+impl Boundary {
+    /// Where `pos + delta` lands on an axis of `len` cells, or `None` if it's outside
+    /// of the grid.
+    fn resolve(self, pos: usize, delta: isize, len: usize) -> Option<usize> {
+        if let Some(pos) = pos.checked_add_signed(delta).filter(|pos| *pos < len)
+        {
+            return Some(pos);
+        }
+
+        let len = isize::try_from(len).unwrap();
+        let pos = isize::try_from(pos).unwrap() + delta;
+
+        let pos = match self {
+            Self::Empty | Self::Occupied => return None,
+            Self::Toroidal => pos.rem_euclid(len),
+            Self::Reflect => {
+                let pos = pos.rem_euclid(2 * len);
+
+                if pos < len { pos } else { 2 * len - 1 - pos }
+            }
+        };
+
+        usize::try_from(pos).ok()
+    }
+
+    /// The cells outside of the grid.
+    const fn outside(self) -> u8 {
+        matches!(self, Self::Occupied) as u8
+    }
+}
+
+/// Number of rolls with fewer than four rolls around them, with the cells beyond
+/// the edges following `boundary`.
+///
+/// `grid` must be surrounded by a ring of cells, see [`pad`], which is replaced
+/// according to `boundary`.
+#[must_use]
+pub fn accessible(grid: &Grid, boundary: Boundary) -> usize {
+    repad(grid, boundary)
         .windows((3, 3))
         .into_iter()
         .filter(|w| w[(1, 1)] == 1)
@@ -32,59 +97,729 @@ fn part1(input: &Grid) -> usize {
         .count()
 }
 
-#[aoc(day4, part2)]
-fn part2(input: &Grid) -> usize {
+/// Number of rolls removed by repeatedly removing the accessible ones, with the
+/// cells beyond the edges following `boundary`.
+///
+/// `grid` must be surrounded by a ring of cells, see [`pad`], which is replaced
+/// according to `boundary`.
+#[must_use]
+pub fn removable(grid: &Grid, boundary: Boundary) -> usize {
+    removals(grid, boundary)
+        .map(|removed| removed.iter().filter(|cell| **cell == 1).count())
+        .sum()
+}
+
+/// The rolls removed by each generation of [`next_grid`], as a grid without padding
+/// where they are `1`, until a generation removes none.
+fn removals(grid: &Grid, boundary: Boundary) -> impl Iterator<Item = Grid> {
+    let mut grid = repad(grid, boundary).into_owned();
+
+    std::iter::from_fn(move || {
+        let next = next_grid(&grid, boundary);
+
+        // The ring follows the rolls it mirrors, so only the inner cells are compared.
+        #[allow(clippy::reversed_empty_ranges)]
+        let removed = (&next ^ &grid).slice_move(s![1..-1, 1..-1]);
+
+        grid = next;
+        removed.iter().any(|cell| *cell == 1).then_some(removed)
+    })
+}
+
+fn repad(grid: &Grid, boundary: Boundary) -> Cow<'_, Grid> {
+    if boundary == Boundary::Empty {
+        return Cow::Borrowed(grid);
+    }
+
+    #[allow(clippy::reversed_empty_ranges)]
+    Cow::Owned(pad_with(&grid.slice(s![1..-1, 1..-1]).to_owned(), boundary))
+}
+
+#[aoc_generator(day4, part1, bitboard)]
+fn parse_bitboard_part1(input: &str) -> BitGrid {
+    BitGrid::from(&parse(input))
+}
+
+#[aoc(day4, part1, bitboard)]
+fn part1_bitboard(input: &BitGrid) -> usize {
+    input.accessible().count()
+}
+
+#[aoc_generator(day4, part2, bitboard)]
+fn parse_bitboard_part2(input: &str) -> BitGrid {
+    BitGrid::from(&parse(input))
+}
+
+#[aoc(day4, part2, bitboard)]
+fn part2_bitboard(input: &BitGrid) -> usize {
     let mut grid = input.clone();
     let mut rolls = 0;
 
     loop {
-        let next = next_grid(&grid);
-
-        let diff = (&next ^ grid).into_iter().filter(|cell| *cell == 1).count();
+        let accessible = grid.accessible();
+        let diff = accessible.count();
 
         if diff == 0 {
             break rolls;
         }
 
         rolls += diff;
-        grid = next;
+        grid.remove(&accessible);
+    }
+}
+
+#[aoc(day4, part2, worklist)]
+fn part2_worklist(input: &Grid) -> usize {
+    remove_all(input).rolls
+}
+
+/// Outcome of removing accessible rolls until none are left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Removal {
+    pub rolls: usize,
+    /// Number of generations that removed at least one roll.
+    pub waves: usize,
+}
+
+/// Same as `part2`, but only the neighbours of removed rolls are checked again, so
+/// the work is proportional to the number of removals rather than to the size of
+/// the grid times the number of waves.
+///
+/// `grid` must be surrounded by empty cells, see [`pad`].
+#[must_use]
+pub fn remove_all(grid: &Grid) -> Removal {
+    let (rows, cols) = grid.dim();
+    let mut rolls = grid.iter().map(|cell| *cell == 1).collect_vec();
+    let neighbours = |idx: usize| {
+        [
+            idx - cols - 1,
+            idx - cols,
+            idx - cols + 1,
+            idx - 1,
+            idx + 1,
+            idx + cols - 1,
+            idx + cols,
+            idx + cols + 1,
+        ]
+    };
+
+    // Padding cells never hold rolls, they're only given a count high enough not to
+    // underflow.
+    let mut counts = vec![8u8; rolls.len()];
+    let mut queued = vec![false; rolls.len()];
+    let mut wave = Vec::new();
+
+    for row in 1..rows - 1 {
+        for col in 1..cols - 1 {
+            let idx = row * cols + col;
+
+            counts[idx] = neighbours(idx)
+                .into_iter()
+                .map(|n| u8::from(rolls[n]))
+                .sum();
+
+            if rolls[idx] && counts[idx] < 4 {
+                queued[idx] = true;
+                wave.push(idx);
+            }
+        }
+    }
+
+    let mut removal = Removal::default();
+
+    while !wave.is_empty() {
+        removal.rolls += wave.len();
+        removal.waves += 1;
+
+        for idx in &wave {
+            rolls[*idx] = false;
+        }
+
+        let mut next = Vec::new();
+
+        for n in wave.into_iter().flat_map(neighbours) {
+            counts[n] -= 1;
+
+            if rolls[n] && !queued[n] && counts[n] < 4 {
+                queued[n] = true;
+                next.push(n);
+            }
+        }
+
+        wave = next;
+    }
+
+    removal
+}
+
+#[aoc(day4, part1, automaton)]
+fn part1_automaton(input: &Grid) -> usize {
+    Automaton::new(input, Rule::FORKLIFT).step()
+}
+
+#[aoc(day4, part2, automaton)]
+fn part2_automaton(input: &Grid) -> usize {
+    Automaton::new(input, Rule::FORKLIFT).run().rolls
+}
+
+/// Which cells around a roll are counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Cells within the given Chebyshev distance, i.e. a square.
+    Moore(usize),
+    /// Cells within the given Manhattan distance, i.e. a diamond.
+    VonNeumann(usize),
+}
+
+impl Neighbourhood {
+    fn offsets(self) -> Vec<(isize, isize)> {
+        let (Self::Moore(radius) | Self::VonNeumann(radius)) = self;
+        let radius = isize::try_from(radius).unwrap();
+
+        (-radius..=radius)
+            .cartesian_product(-radius..=radius)
+            .filter(|offset| *offset != (0, 0))
+            .filter(|(dr, dc)| match self {
+                Self::Moore(_) => true,
+                Self::VonNeumann(_) => dr.abs() + dc.abs() <= radius,
+            })
+            .collect()
+    }
+}
+
+/// When removed rolls stop counting for the rolls around them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Update {
+    /// A generation is decided on the grid as it was before it, like `next_grid`.
+    Simultaneous,
+    /// A generation scans the grid row by row, and each removal is seen by the rolls
+    /// checked after it.
+    Sequential,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub neighbourhood: Neighbourhood,
+    /// A roll survives with at least this many rolls in its neighbourhood, itself
+    /// included.
+    pub survival: usize,
+    pub update: Update,
+    pub boundary: Boundary,
+}
+
+impl Rule {
+    /// The forklift rule of the puzzle.
+    pub const FORKLIFT: Self = Self {
+        neighbourhood: Neighbourhood::Moore(1),
+        survival: 5,
+        update: Update::Simultaneous,
+        boundary: Boundary::Empty,
+    };
+}
+
+/// Removes rolls from a grid according to a [`Rule`], one generation at a time.
+#[derive(Clone, Debug)]
+pub struct Automaton {
+    /// Not padded, cells outside of it follow the rule's boundary.
+    grid: Grid,
+    rule: Rule,
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Automaton {
+    /// `grid` must be surrounded by empty cells, see [`pad`].
+    #[must_use]
+    pub fn new(grid: &Grid, rule: Rule) -> Self {
+        #[allow(clippy::reversed_empty_ranges)]
+        let grid = grid.slice(s![1..-1, 1..-1]).to_owned();
+
+        Self {
+            grid,
+            rule,
+            offsets: rule.neighbourhood.offsets(),
+        }
+    }
+
+    /// The current grid, without padding.
+    #[must_use]
+    pub const fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Whether the roll at `(row, col)` survives in the current grid.
+    #[must_use]
+    pub fn survives(&self, row: usize, col: usize) -> bool {
+        let (rows, cols) = self.grid.dim();
+        let boundary = self.rule.boundary;
+        let neighbours = self
+            .offsets
+            .iter()
+            .map(|(dr, dc)| {
+                match (
+                    boundary.resolve(row, *dr, rows),
+                    boundary.resolve(col, *dc, cols),
+                ) {
+                    (Some(row), Some(col)) => self.grid[(row, col)],
+                    _ => boundary.outside(),
+                }
+            })
+            .filter(|cell| *cell == 1)
+            .count();
+
+        neighbours + 1 >= self.rule.survival
+    }
+
+    /// Runs a single generation, returning the number of rolls removed.
+    pub fn step(&mut self) -> usize {
+        let rolls = self
+            .grid
+            .indexed_iter()
+            .filter(|(_, cell)| **cell == 1)
+            .map(|(pos, _)| pos)
+            .collect_vec();
+
+        match self.rule.update {
+            Update::Simultaneous => {
+                let removed = rolls
+                    .into_iter()
+                    .filter(|(row, col)| !self.survives(*row, *col))
+                    .collect_vec();
+
+                for pos in &removed {
+                    self.grid[*pos] = 0;
+                }
+
+                removed.len()
+            }
+            Update::Sequential => rolls
+                .into_iter()
+                .filter(|(row, col)| {
+                    let removed = !self.survives(*row, *col);
+
+                    if removed {
+                        self.grid[(*row, *col)] = 0;
+                    }
+
+                    removed
+                })
+                .count(),
+        }
+    }
+
+    /// Runs generations until no roll is removed.
+    pub fn run(&mut self) -> Removal {
+        let mut removal = Removal::default();
+
+        loop {
+            let rolls = self.step();
+
+            if rolls == 0 {
+                break removal;
+            }
+
+            removal.rolls += rolls;
+            removal.waves += 1;
+        }
     }
 }
 
-fn pad(grid: &Grid) -> Grid {
+/// Surrounds `grid` with a ring of empty cells.
+#[must_use]
+pub fn pad(grid: &Grid) -> Grid {
+    pad_with(grid, Boundary::Empty)
+}
+
+/// Surrounds `grid` with a ring of cells following `boundary`.
+#[must_use]
+pub fn pad_with(grid: &Grid, boundary: Boundary) -> Grid {
     let (rows, cols) = (grid.nrows(), grid.ncols());
 
-    let mut padded = Grid::zeros((rows + 2, cols + 2));
+    let mut padded = Grid::from_elem((rows + 2, cols + 2), boundary.outside());
 
     #[allow(clippy::reversed_empty_ranges)]
     padded.slice_mut(s![1..-1, 1..-1]).assign(grid);
 
+    if matches!(boundary, Boundary::Toroidal | Boundary::Reflect) {
+        let ring = (0..rows + 2)
+            .flat_map(|row| [(row, 0), (row, cols + 1)])
+            .chain((1..=cols).flat_map(|col| [(0, col), (rows + 1, col)]));
+
+        for (row, col) in ring {
+            if let (Some(inner_row), Some(inner_col)) = (
+                boundary.resolve(row, -1, rows),
+                boundary.resolve(col, -1, cols),
+            ) {
+                padded[(row, col)] = grid[(inner_row, inner_col)];
+            }
+        }
+    }
+
     padded
 }
 
-fn next_grid(grid: &Grid) -> Grid {
-    pad(&Grid::from_shape_vec(
-        (grid.nrows() - 2, grid.ncols() - 2),
-        grid.windows((3, 3))
-            .into_iter()
-            .map(|w| {
-                let center = w[(1, 1)];
+fn next_grid(grid: &Grid, boundary: Boundary) -> Grid {
+    pad_with(
+        &Grid::from_shape_vec(
+            (grid.nrows() - 2, grid.ncols() - 2),
+            grid.windows((3, 3))
+                .into_iter()
+                .map(|w| {
+                    let center = w[(1, 1)];
+
+                    if center == 0 {
+                        return 0;
+                    }
+
+                    // PERF: benchmarks unintuitively show that this is faster than `w.sum() < 5`
+                    u8::from(
+                        w.into_iter().filter(|cell| **cell == 1).count() >= 5,
+                    )
+                })
+                .collect(),
+        )
+        .unwrap(),
+        boundary,
+    )
+}
+
+/// A grid of rolls packed one bit per cell, each row taking a `u64` word per 64
+/// columns. Column `col` is bit `col % 64` of the row's `col / 64`-th word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitGrid {
+    rows: usize,
+    cols: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    #[must_use]
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let stride = cols.div_ceil(64);
+
+        Self {
+            rows,
+            cols,
+            stride,
+            words: vec![0; rows * stride],
+        }
+    }
+
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub const fn cols(&self) -> usize {
+        self.cols
+    }
+
+    #[must_use]
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.words[row * self.stride + col / 64] >> (col % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, roll: bool) {
+        let word = &mut self.words[row * self.stride + col / 64];
 
-                if center == 0 {
-                    return 0;
+        if roll {
+            *word |= 1 << (col % 64);
+        } else {
+            *word &= !(1 << (col % 64));
+        }
+    }
+
+    /// Number of rolls in the grid.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Removes every roll of `other` from the grid.
+    pub fn remove(&mut self, other: &Self) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other;
+        }
+    }
+
+    /// The rolls with fewer than four rolls among their eight neighbours.
+    #[must_use]
+    pub fn accessible(&self) -> Self {
+        let empty = vec![0; self.stride];
+        let row = |row: Option<usize>| {
+            row.filter(|row| *row < self.rows)
+                .map_or(&empty[..], |row| {
+                    &self.words[row * self.stride..(row + 1) * self.stride]
+                })
+        };
+
+        let mut accessible = Self::new(self.rows, self.cols);
+
+        for r in 0..self.rows {
+            let above = row(r.checked_sub(1));
+            let centre = row(Some(r));
+            let below = row(Some(r + 1));
+
+            for k in 0..self.stride {
+                let [aw, a, ae] = neighbours(above, k);
+                let [cw, c, ce] = neighbours(centre, k);
+                let [bw, b, be] = neighbours(below, k);
+
+                // Bit-sliced counters: bit `i` of `planes[p]` is bit `p` of the number
+                // of rolls around column `i`.
+                let mut planes = [0u64; 4];
+
+                for neighbour in [aw, a, ae, cw, ce, bw, b, be] {
+                    let mut carry = neighbour;
+
+                    for plane in &mut planes {
+                        let next = *plane & carry;
+                        *plane ^= carry;
+                        carry = next;
+                    }
                 }
 
-                // PERF: benchmarks unintuitively show that this is faster than `w.sum() < 5`
-                u8::from(w.into_iter().filter(|cell| **cell == 1).count() >= 5)
+                let crowded = planes[2] | planes[3];
+
+                accessible.words[r * self.stride + k] = c & !crowded;
+            }
+        }
+
+        accessible
+    }
+}
+
+/// The `k`-th word of `row`, shifted so that each bit lines up with the cell to its
+/// west, itself, and the cell to its east.
+fn neighbours(row: &[u64], k: usize) -> [u64; 3] {
+    let word = row[k];
+    let prev = if k > 0 { row[k - 1] } else { 0 };
+    let next = row.get(k + 1).copied().unwrap_or(0);
+
+    [(word << 1) | (prev >> 63), word, (word >> 1) | (next << 63)]
+}
+
+impl From<&Grid> for BitGrid {
+    /// Converts a padded grid, as returned by `parse`.
+    fn from(grid: &Grid) -> Self {
+        let mut bits = Self::new(grid.nrows() - 2, grid.ncols() - 2);
+
+        for ((row, col), cell) in grid.indexed_iter() {
+            if *cell == 1 {
+                bits.set(row - 1, col - 1, true);
+            }
+        }
+
+        bits
+    }
+}
+
+/// Colours of removed rolls, cycled through by wave.
+const PALETTE: [[u8; 3]; 10] = [
+    [0x4e, 0x79, 0xa7],
+    [0xf2, 0x8e, 0x2b],
+    [0xe1, 0x57, 0x59],
+    [0x76, 0xb7, 0xb2],
+    [0x59, 0xa1, 0x4f],
+    [0xed, 0xc9, 0x48],
+    [0xb0, 0x7a, 0xa1],
+    [0xff, 0x9d, 0xa7],
+    [0x9c, 0x75, 0x5f],
+    [0xba, 0xb0, 0xac],
+];
+
+/// Milliseconds each wave lasts in the SVG animation.
+const SVG_WAVE_MS: usize = 500;
+
+/// The generations of `part2`, recorded to be rendered as images.
+///
+/// Frame `0` is the initial grid, frame `n` the grid after `n` waves of removals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Animation {
+    /// For each cell without padding: `None` if empty, `Some(0)` for rolls never
+    /// removed, `Some(wave)` for rolls removed in that (1-based) wave.
+    waves: Array2<Option<usize>>,
+    /// Rolls removed by each wave.
+    counts: Vec<usize>,
+}
+
+impl Animation {
+    /// `grid` must be surrounded by empty cells, see [`pad`].
+    #[must_use]
+    pub fn new(grid: &Grid) -> Self {
+        #[allow(clippy::reversed_empty_ranges)]
+        let mut waves = grid
+            .slice(s![1..-1, 1..-1])
+            .map(|cell| (*cell == 1).then_some(0));
+        let mut counts = Vec::new();
+
+        for removed in removals(grid, Boundary::Empty) {
+            counts.push(removed.iter().filter(|cell| **cell == 1).count());
+            waves.zip_mut_with(&removed, |wave, removed| {
+                if *removed == 1 {
+                    *wave = Some(counts.len());
+                }
+            });
+        }
+
+        Self { waves, counts }
+    }
+
+    /// Number of frames, the initial grid included.
+    #[must_use]
+    pub const fn frames(&self) -> usize {
+        self.counts.len() + 1
+    }
+
+    /// Rolls removed by each wave.
+    #[must_use]
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Binary PGM image of `frame`: rolls are black, empty cells white and removed
+    /// rolls grey, lighter the earlier they were removed.
+    #[must_use]
+    pub fn to_pgm(&self, frame: usize) -> Vec<u8> {
+        let waves = self.counts.len().max(1);
+
+        self.image("P5", frame, &[0], |removed| {
+            removed.map_or_else(
+                || vec![0xff],
+                |wave| {
+                    let shade = 0xe0 - (0xe0 - 0x40) * (wave - 1) / waves;
+
+                    vec![u8::try_from(shade).unwrap_or(0x40)]
+                },
+            )
+        })
+    }
+
+    /// Binary PPM image of `frame`: rolls are black, empty cells white and removed
+    /// rolls coloured by wave.
+    #[must_use]
+    pub fn to_ppm(&self, frame: usize) -> Vec<u8> {
+        self.image("P6", frame, &[0; 3], |removed| {
+            removed.map_or_else(
+                || vec![0xff; 3],
+                |wave| PALETTE[(wave - 1) % PALETTE.len()].to_vec(),
+            )
+        })
+    }
+
+    /// `pixel` is given `None` for empty cells and the wave of removed rolls,
+    /// remaining rolls are `roll`.
+    fn image(
+        &self,
+        magic: &str,
+        frame: usize,
+        roll: &[u8],
+        pixel: impl Fn(Option<usize>) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let (rows, cols) = self.waves.dim();
+        let mut image = format!("{magic}\n{cols} {rows}\n255\n").into_bytes();
+
+        for wave in &self.waves {
+            match wave {
+                Some(wave) if *wave == 0 || *wave > frame => {
+                    image.extend_from_slice(roll);
+                }
+                Some(wave) => image.extend(pixel(Some(*wave))),
+                None => image.extend(pixel(None)),
+            }
+        }
+
+        image
+    }
+
+    /// SVG animation of all the waves, where each removed roll takes the colour of
+    /// its wave.
+    #[must_use]
+    pub fn to_svg(&self) -> String {
+        let (rows, cols) = self.waves.dim();
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {cols} {rows}" shape-rendering="crispEdges">
+<rect width="{cols}" height="{rows}" fill="white"/>
+"#
+        );
+
+        for wave in 0..=self.counts.len() {
+            let set = if wave > 0 {
+                let [r, g, b] = PALETTE[(wave - 1) % PALETTE.len()];
+
+                format!(
+                    r##"<set attributeName="fill" to="#{r:02x}{g:02x}{b:02x}" begin="{}ms" fill="freeze"/>"##,
+                    SVG_WAVE_MS * wave
+                )
+            } else {
+                String::new()
+            };
+
+            let rects = self
+                .waves
+                .indexed_iter()
+                .filter(|(_, w)| **w == Some(wave))
+                .map(|((row, col), _)| {
+                    format!(r#"<rect x="{col}" y="{row}" width="1" height="1"/>"#)
+                })
+                .join("");
+
+            svg.push_str(r#"<g fill="black">"#);
+            svg.push_str(&set);
+            svg.push_str(&rects);
+            svg.push_str("</g>\n");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// CSV of the rolls removed by each wave, and the rolls left after it.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut remaining = self.waves.iter().filter(|w| w.is_some()).count();
+        let rows = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(wave, count)| {
+                remaining -= count;
+                format!("{},{count},{remaining}\n", wave + 1)
             })
-            .collect(),
-    )
-    .unwrap())
+            .join("");
+
+        format!("wave,removed,remaining\n{rows}")
+    }
+
+    /// Writes `frameNNNN.pgm` and `frameNNNN.ppm` for each frame, `removal.svg` and
+    /// `waves.csv` into `dir`, which must exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the files cannot be written.
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        for frame in 0..self.frames() {
+            fs::write(
+                dir.join(format!("frame{frame:04}.pgm")),
+                self.to_pgm(frame),
+            )?;
+            fs::write(
+                dir.join(format!("frame{frame:04}.ppm")),
+                self.to_ppm(frame),
+            )?;
+        }
+
+        fs::write(dir.join("removal.svg"), self.to_svg())?;
+        fs::write(dir.join("waves.csv"), self.to_csv())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::prelude::*;
+    use std::path::PathBuf;
 
     const EXAMPLE: &str = "..@@.@@@@.
 @@@.@.@.@@
@@ -116,4 +851,368 @@ mod tests {
     fn solution2() {
         assert_eq!(part2(&parse(include_str!("../input/2025/day4.txt"))), 9_609);
     }
+
+    #[test]
+    fn part1_bitboard_example() {
+        assert_eq!(part1_bitboard(&parse_bitboard_part1(EXAMPLE)), 13);
+    }
+
+    #[test]
+    fn solution1_bitboard() {
+        assert_eq!(
+            part1_bitboard(&parse_bitboard_part1(include_str!(
+                "../input/2025/day4.txt"
+            ))),
+            1_560
+        );
+    }
+
+    #[test]
+    fn part2_bitboard_example() {
+        assert_eq!(part2_bitboard(&parse_bitboard_part2(EXAMPLE)), 43);
+    }
+
+    #[test]
+    fn solution2_bitboard() {
+        assert_eq!(
+            part2_bitboard(&parse_bitboard_part2(include_str!(
+                "../input/2025/day4.txt"
+            ))),
+            9_609
+        );
+    }
+
+    #[test]
+    fn bitboard_matches_ndarray() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for (rows, cols) in [(1, 1), (3, 64), (64, 65), (130, 200), (257, 129)] {
+            let grid = pad(&Grid::from_shape_fn((rows, cols), |_| {
+                u8::from(rng.random_bool(0.7))
+            }));
+            let bits = BitGrid::from(&grid);
+
+            assert_eq!(part1_bitboard(&bits), part1(&grid), "{rows}x{cols}");
+            assert_eq!(part2_bitboard(&bits), part2(&grid), "{rows}x{cols}");
+        }
+    }
+
+    #[test]
+    fn part2_worklist_example() {
+        assert_eq!(part2_worklist(&parse(EXAMPLE)), 43);
+    }
+
+    #[test]
+    fn solution2_worklist() {
+        assert_eq!(
+            part2_worklist(&parse(include_str!("../input/2025/day4.txt"))),
+            9_609
+        );
+    }
+
+    #[test]
+    fn worklist_matches_ndarray() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for (rows, cols) in [(1, 1), (1, 7), (9, 1), (40, 60), (130, 90)] {
+            let grid = pad(&Grid::from_shape_fn((rows, cols), |_| {
+                u8::from(rng.random_bool(0.7))
+            }));
+            let removal = remove_all(&grid);
+            let mut expected = Removal::default();
+
+            for removed in removals(&grid, Boundary::Empty) {
+                expected.rolls += removed.iter().filter(|c| **c == 1).count();
+                expected.waves += 1;
+            }
+
+            assert_eq!(removal, expected, "{rows}x{cols}");
+        }
+    }
+
+    #[test]
+    fn worklist_waves() {
+        assert_eq!(
+            remove_all(&parse(EXAMPLE)),
+            Removal {
+                rolls: 43,
+                waves: 9
+            }
+        );
+    }
+
+    #[test]
+    fn part1_automaton_example() {
+        assert_eq!(part1_automaton(&parse(EXAMPLE)), 13);
+    }
+
+    #[test]
+    fn part2_automaton_example() {
+        assert_eq!(part2_automaton(&parse(EXAMPLE)), 43);
+        assert_eq!(
+            Automaton::new(&parse(EXAMPLE), Rule::FORKLIFT).run(),
+            remove_all(&parse(EXAMPLE))
+        );
+    }
+
+    #[test]
+    fn solution2_automaton() {
+        assert_eq!(
+            part2_automaton(&parse(include_str!("../input/2025/day4.txt"))),
+            9_609
+        );
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        assert_eq!(Neighbourhood::Moore(1).offsets().len(), 8);
+        assert_eq!(Neighbourhood::Moore(2).offsets().len(), 24);
+        assert_eq!(Neighbourhood::VonNeumann(1).offsets().len(), 4);
+        assert_eq!(Neighbourhood::VonNeumann(2).offsets().len(), 12);
+        assert!(Neighbourhood::Moore(0).offsets().is_empty());
+    }
+
+    #[test]
+    fn automaton_updates() {
+        let grid = parse("@@@@");
+        let rule = Rule {
+            neighbourhood: Neighbourhood::Moore(1),
+            survival: 3,
+            update: Update::Simultaneous,
+            boundary: Boundary::Empty,
+        };
+
+        assert_eq!(
+            Automaton::new(&grid, rule).run(),
+            Removal { rolls: 4, waves: 2 }
+        );
+
+        let mut automaton = Automaton::new(
+            &grid,
+            Rule {
+                update: Update::Sequential,
+                ..rule
+            },
+        );
+
+        assert_eq!(automaton.run(), Removal { rolls: 4, waves: 1 });
+        assert_eq!(automaton.grid(), Grid::zeros((1, 4)));
+    }
+
+    #[test]
+    fn automaton_von_neumann() {
+        let grid = parse(
+            ".@.
+@@@
+.@.",
+        );
+        let mut automaton = Automaton::new(
+            &grid,
+            Rule {
+                neighbourhood: Neighbourhood::VonNeumann(1),
+                survival: 3,
+                update: Update::Simultaneous,
+                boundary: Boundary::Empty,
+            },
+        );
+
+        assert!(automaton.survives(1, 1));
+        assert!(!automaton.survives(0, 1));
+        assert_eq!(automaton.step(), 4);
+        assert_eq!(automaton.step(), 1);
+        assert_eq!(automaton.step(), 0);
+
+        let mut automaton = Automaton::new(
+            &grid,
+            Rule {
+                neighbourhood: Neighbourhood::Moore(2),
+                survival: 5,
+                update: Update::Simultaneous,
+                boundary: Boundary::Empty,
+            },
+        );
+
+        assert_eq!(automaton.run(), Removal { rolls: 0, waves: 0 });
+    }
+
+    #[test]
+    fn animation() {
+        let animation = Animation::new(&parse(EXAMPLE));
+
+        assert_eq!(animation.frames(), 10);
+        assert_eq!(animation.counts(), [13, 12, 7, 5, 2, 1, 1, 1, 1]);
+        assert!(animation.to_csv().starts_with(
+            "wave,removed,remaining
+1,13,58
+2,12,46
+"
+        ));
+        assert!(animation.to_csv().ends_with("9,1,28\n"));
+    }
+
+    #[test]
+    fn animation_images() {
+        let animation = Animation::new(&parse("@@@\n.@."));
+
+        assert_eq!(animation.counts(), [4]);
+        assert_eq!(
+            animation.to_pgm(0),
+            b"P5\n3 2\n255\n\x00\x00\x00\xff\x00\xff"
+        );
+        assert_eq!(
+            animation.to_pgm(1),
+            b"P5\n3 2\n255\n\xe0\xe0\xe0\xff\xe0\xff"
+        );
+        assert_eq!(animation.to_ppm(0).len(), b"P6\n3 2\n255\n".len() + 18);
+        assert_eq!(
+            animation.to_ppm(1)[b"P6\n3 2\n255\n".len()..][..6],
+            [0x4e, 0x79, 0xa7, 0x4e, 0x79, 0xa7]
+        );
+
+        let svg = animation.to_svg();
+
+        assert_eq!(svg.matches("<rect ").count(), 5);
+        assert!(svg.contains(r##"<set attributeName="fill" to="#4e79a7" begin="500ms" fill="freeze"/>"##));
+    }
+
+    /// A fresh directory, removed with everything in it when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(prefix: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "{prefix}-{}-{:016x}",
+                std::process::id(),
+                rand::random::<u64>()
+            ));
+            fs::create_dir(&dir).unwrap();
+
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn animation_write() {
+        let tmp = TempDir::new("aoc-2025-day4-animation");
+        let dir = &tmp.0;
+
+        let animation = Animation::new(&parse(EXAMPLE));
+        animation.write(dir).unwrap();
+
+        assert_eq!(
+            fs::read(dir.join("frame0009.pgm")).unwrap(),
+            animation.to_pgm(9)
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("waves.csv")).unwrap(),
+            animation.to_csv()
+        );
+        assert!(dir.join("removal.svg").exists());
+    }
+
+    #[test]
+    fn pad_with_boundaries() {
+        let grid = array![[1, 0, 0], [0, 0, 1]];
+
+        assert_eq!(
+            pad_with(&grid, Boundary::Empty),
+            array![
+                [0, 0, 0, 0, 0],
+                [0, 1, 0, 0, 0],
+                [0, 0, 0, 1, 0],
+                [0, 0, 0, 0, 0]
+            ]
+        );
+        assert_eq!(
+            pad_with(&grid, Boundary::Occupied),
+            array![
+                [1, 1, 1, 1, 1],
+                [1, 1, 0, 0, 1],
+                [1, 0, 0, 1, 1],
+                [1, 1, 1, 1, 1]
+            ]
+        );
+        assert_eq!(
+            pad_with(&grid, Boundary::Toroidal),
+            array![
+                [1, 0, 0, 1, 0],
+                [0, 1, 0, 0, 1],
+                [1, 0, 0, 1, 0],
+                [0, 1, 0, 0, 1]
+            ]
+        );
+        assert_eq!(
+            pad_with(&grid, Boundary::Reflect),
+            array![
+                [1, 1, 0, 0, 0],
+                [1, 1, 0, 0, 0],
+                [0, 0, 0, 1, 1],
+                [0, 0, 0, 1, 1]
+            ]
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        let full = parse(
+            "@@@@
+@@@@
+@@@@",
+        );
+
+        assert_eq!(accessible(&full, Boundary::Empty), 4);
+        assert_eq!(removable(&full, Boundary::Empty), 12);
+
+        for boundary in
+            [Boundary::Occupied, Boundary::Toroidal, Boundary::Reflect]
+        {
+            assert_eq!(accessible(&full, boundary), 0, "{boundary:?}");
+            assert_eq!(removable(&full, boundary), 0, "{boundary:?}");
+        }
+
+        let input = parse(include_str!("../input/2025/day4.txt"));
+
+        assert_eq!(accessible(&input, Boundary::Empty), 1_560);
+        assert_eq!(removable(&input, Boundary::Empty), 9_609);
+    }
+
+    #[test]
+    fn boundaries_match_automaton() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for (rows, cols) in [(1, 1), (1, 5), (4, 1), (2, 2), (17, 23), (40, 31)] {
+            let grid = pad(&Grid::from_shape_fn((rows, cols), |_| {
+                u8::from(rng.random_bool(0.7))
+            }));
+
+            for boundary in [
+                Boundary::Empty,
+                Boundary::Occupied,
+                Boundary::Toroidal,
+                Boundary::Reflect,
+            ] {
+                let rule = Rule {
+                    boundary,
+                    ..Rule::FORKLIFT
+                };
+
+                assert_eq!(
+                    accessible(&grid, boundary),
+                    Automaton::new(&grid, rule).step(),
+                    "{rows}x{cols} {boundary:?}"
+                );
+                assert_eq!(
+                    removable(&grid, boundary),
+                    Automaton::new(&grid, rule).run().rolls,
+                    "{rows}x{cols} {boundary:?}"
+                );
+            }
+        }
+    }
 }
@@ -1,10 +1,11 @@
-use crate::rangeset::RangeSet;
+use crate::{coverage::Coverage, rangemap::RangeMap, rangeset::RangeSet};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
+use std::ops::RangeInclusive;
 
-type Id = u64;
+pub type Id = u64;
 
-#[aoc_generator(day5)]
-fn parse(input: &str) -> (RangeSet<Id>, Vec<Id>) {
+fn parse_raw(input: &str) -> (Vec<RangeInclusive<Id>>, Vec<Id>) {
     let input = input.trim_ascii();
     let (ranges, ingredients) = input.split_once("\n\n").unwrap();
 
@@ -12,12 +13,9 @@ fn parse(input: &str) -> (RangeSet<Id>, Vec<Id>) {
         .lines()
         .map(|line| {
             let (from, to) = line.split_once('-').unwrap();
-            from.parse::<Id>().unwrap()..to.parse::<Id>().unwrap() + 1
+            from.parse::<Id>().unwrap()..=to.parse::<Id>().unwrap()
         })
-        .fold(RangeSet::default(), |mut acc, range| {
-            acc.insert_range(&range);
-            acc
-        });
+        .collect();
 
     let ingredients = ingredients
         .lines()
@@ -27,17 +25,86 @@ fn parse(input: &str) -> (RangeSet<Id>, Vec<Id>) {
     (ranges, ingredients)
 }
 
+#[aoc_generator(day5)]
+fn parse(input: &str) -> (RangeSet<Id>, Vec<Id>) {
+    let (ranges, ingredients) = parse_raw(input);
+
+    (ranges.into_iter().collect(), ingredients)
+}
+
 #[aoc(day5, part1)]
 fn part1((ranges, ingredients): &(RangeSet<Id>, Vec<Id>)) -> usize {
-    ingredients
-        .iter()
-        .filter(|id| ranges.iter().any(|range| range.contains(id)))
-        .count()
+    ingredients.iter().filter(|id| ranges.contains(id)).count()
+}
+
+#[aoc(day5, part1, sweep)]
+fn part1_sweep((ranges, ingredients): &(RangeSet<Id>, Vec<Id>)) -> usize {
+    ranges.count_contained(ingredients.iter().sorted())
 }
 
 #[aoc(day5, part2)]
-fn part2((ranges, _ingredients): &(RangeSet<Id>, Vec<Id>)) -> u64 {
-    ranges.iter().map(|r| r.end - r.start).sum()
+fn part2((ranges, _ingredients): &(RangeSet<Id>, Vec<Id>)) -> u128 {
+    ranges.len()
+}
+
+/// How many ingredients each range of the database declares fresh. Ingredients in
+/// overlapping ranges count for each of them.
+///
+/// # Panics
+///
+/// Panics if the input is malformed.
+#[must_use]
+pub fn hits_per_range(input: &str) -> Vec<(RangeInclusive<Id>, usize)> {
+    let (ranges, mut ingredients) = parse_raw(input);
+
+    ingredients.sort_unstable();
+
+    ranges
+        .into_iter()
+        .map(|range| {
+            let first = ingredients.partition_point(|id| id < range.start());
+            let last = ingredients.partition_point(|id| id <= range.end());
+
+            (range, last - first)
+        })
+        .collect()
+}
+
+/// How many ingredients each range of the database is responsible for. When ranges
+/// overlap, an ingredient is attributed to the last one listed.
+///
+/// # Panics
+///
+/// Panics if the input is malformed.
+#[must_use]
+pub fn owner_hits(input: &str) -> Vec<(RangeInclusive<Id>, usize)> {
+    let (ranges, ingredients) = parse_raw(input);
+
+    let owners = ranges
+        .iter()
+        .cloned()
+        .zip(0..)
+        .collect::<RangeMap<Id, usize>>();
+
+    let mut hits = vec![0; ranges.len()];
+
+    for id in ingredients {
+        if let Some(idx) = owners.get(&id) {
+            hits[*idx] += 1;
+        }
+    }
+
+    ranges.into_iter().zip(hits).collect()
+}
+
+/// How many ranges of the database declare each ingredient fresh.
+///
+/// # Panics
+///
+/// Panics if the input is malformed.
+#[must_use]
+pub fn coverage(input: &str) -> Coverage<Id> {
+    parse_raw(input).0.into_iter().collect()
 }
 
 #[cfg(test)]
@@ -66,6 +133,19 @@ mod tests {
         assert_eq!(part1(&parse(include_str!("../input/2025/day5.txt"))), 615);
     }
 
+    #[test]
+    fn part1_sweep_example() {
+        assert_eq!(part1_sweep(&parse(EXAMPLE)), 3);
+    }
+
+    #[test]
+    fn solution1_sweep() {
+        assert_eq!(
+            part1_sweep(&parse(include_str!("../input/2025/day5.txt"))),
+            615
+        );
+    }
+
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 14);
@@ -78,4 +158,89 @@ mod tests {
             353_716_783_056_994
         );
     }
+
+    #[test]
+    fn ranges_up_to_max() {
+        let input = format!("0-5\n{}-{}\n\n{}", u64::MAX - 1, u64::MAX, u64::MAX);
+        let parsed = parse(&input);
+
+        assert_eq!(part1(&parsed), 1);
+        assert_eq!(part2(&parsed), 8);
+
+        let input = format!("0-{}\n\n0", u64::MAX);
+
+        assert_eq!(part2(&parse(&input)), 1 << 64);
+    }
+
+    #[test]
+    fn hits_per_range_example() {
+        assert_eq!(
+            hits_per_range(EXAMPLE),
+            vec![(3..=5, 1), (10..=14, 1), (16..=20, 1), (12..=18, 1)]
+        );
+        assert_eq!(
+            owner_hits(EXAMPLE),
+            vec![(3..=5, 1), (10..=14, 1), (16..=20, 0), (12..=18, 1)]
+        );
+
+        let input = format!("0-{}\n5-{}\n\n1\n{}", u64::MAX, u64::MAX, u64::MAX);
+
+        assert_eq!(
+            hits_per_range(&input),
+            vec![(0..=u64::MAX, 2), (5..=u64::MAX, 1)]
+        );
+        assert_eq!(
+            owner_hits(&input),
+            vec![(0..=u64::MAX, 1), (5..=u64::MAX, 1)]
+        );
+    }
+
+    #[test]
+    fn hits_per_range_input() {
+        let input = include_str!("../input/2025/day5.txt");
+        let coverage = coverage(input);
+        let depths = parse_raw(input)
+            .1
+            .iter()
+            .map(|id| coverage.depth(id))
+            .sum::<usize>();
+
+        assert_eq!(
+            hits_per_range(input)
+                .iter()
+                .map(|(_, hits)| hits)
+                .sum::<usize>(),
+            depths
+        );
+        assert_eq!(
+            owner_hits(input)
+                .iter()
+                .map(|(_, hits)| hits)
+                .sum::<usize>(),
+            615
+        );
+    }
+
+    #[test]
+    fn coverage_example() {
+        let coverage = coverage(EXAMPLE);
+
+        assert_eq!(coverage.max_depth(), 2);
+        assert_eq!(
+            coverage.at_least(2).iter_inclusive().collect_vec(),
+            vec![12..=14, 16..=18]
+        );
+        assert_eq!(coverage.len_at_least(1), 14);
+        assert_eq!(coverage.len_at_least(2), 6);
+        assert_eq!(coverage.depth(&17), 2);
+        assert_eq!(coverage.depth(&11), 1);
+        assert_eq!(coverage.depth(&32), 0);
+    }
+
+    #[test]
+    fn coverage_input() {
+        let input = include_str!("../input/2025/day5.txt");
+
+        assert_eq!(coverage(input).len_at_least(1), 353_716_783_056_994);
+    }
 }
@@ -1,133 +1,589 @@
+use crate::digits::parse_digit;
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
-use ndarray::prelude::*;
+use std::{fmt, ops::Range};
 
-#[derive(Debug)]
-enum Op {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
     Sum,
     Mul,
+    Sub,
+    /// Exact division, a remainder is an error.
+    Div,
+    Min,
+    Max,
+    Pow,
 }
 
-type Grid = Array2<u64>;
-type Ops = Vec<Op>;
+impl Op {
+    pub const ALL: [Self; 7] = [
+        Self::Sum,
+        Self::Mul,
+        Self::Sub,
+        Self::Div,
+        Self::Min,
+        Self::Max,
+        Self::Pow,
+    ];
 
-#[aoc_generator(day6)]
-fn parse(input: &str) -> String {
-    // NOTE: cargo-aoc does not allow returning refs from `parse` :(
-    input.into()
+    /// Splits `text` into operators, which may or may not be separated by spaces.
+    /// On failure, returns the part that isn't an operator.
+    fn parse_all(text: &str) -> Result<Vec<Self>, &str> {
+        let mut ops = Vec::new();
+        let mut rest = text.trim_start();
+
+        while !rest.is_empty() {
+            let op = Self::ALL
+                .into_iter()
+                .find(|op| rest.starts_with(op.symbol()))
+                .ok_or_else(|| rest.split_whitespace().next().unwrap_or(rest))?;
+
+            ops.push(op);
+            rest = rest[op.symbol().len()..].trim_start();
+        }
+
+        Ok(ops)
+    }
+
+    #[must_use]
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Sum => "+",
+            Self::Mul => "*",
+            Self::Sub => "-",
+            Self::Div => "/",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Pow => "^",
+        }
+    }
+
+    /// How tightly the operator binds in an expression.
+    const fn precedence(self) -> u8 {
+        match self {
+            Self::Min | Self::Max => 0,
+            Self::Sum | Self::Sub => 1,
+            Self::Mul | Self::Div => 2,
+            Self::Pow => 3,
+        }
+    }
+
+    /// `a op b`, checking for overflow.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the result doesn't fit in a `u64`, or on a division with a
+    /// remainder or by zero.
+    pub fn apply(self, a: u64, b: u64) -> Result<u64, EvalError> {
+        let overflow = EvalError::Overflow { op: self, a, b };
+
+        match self {
+            Self::Sum => a.checked_add(b).ok_or(overflow),
+            Self::Mul => a.checked_mul(b).ok_or(overflow),
+            Self::Sub => a.checked_sub(b).ok_or(overflow),
+            Self::Div if b == 0 => Err(EvalError::DivisionByZero { a }),
+            Self::Div if !a.is_multiple_of(b) => {
+                Err(EvalError::InexactDivision { a, b })
+            }
+            Self::Div => Ok(a / b),
+            Self::Min => Ok(a.min(b)),
+            Self::Max => Ok(a.max(b)),
+            // 0 and 1 to any power are themselves, even past `u32::MAX`.
+            Self::Pow if a <= 1 => Ok(if b == 0 { 1 } else { a }),
+            Self::Pow => u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_pow(b))
+                .ok_or(overflow),
+        }
+    }
 }
 
-#[aoc(day6, part1)]
-fn part1(input: &str) -> u64 {
-    solve(parse_grid_horizontally(input))
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    Overflow {
+        op: Op,
+        a: u64,
+        b: u64,
+    },
+    InexactDivision {
+        a: u64,
+        b: u64,
+    },
+    DivisionByZero {
+        a: u64,
+    },
+    /// An operator without an identity applied to no numbers at all.
+    NoOperands {
+        op: Op,
+    },
+    /// The operators don't fit between the numbers.
+    OperatorCount {
+        ops: usize,
+        numbers: usize,
+    },
+    /// A number which doesn't fit in a `u64`. Indices are zero-based, of the digit
+    /// it overflows at.
+    NumberOverflow {
+        row: usize,
+        column: usize,
+    },
 }
 
-#[aoc(day6, part2)]
-fn part2(input: &str) -> u64 {
-    solve(parse_grid_vertically(input))
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow { op, a, b } => {
+                write!(f, "{a} {} {b} overflows", op.symbol())
+            }
+            Self::InexactDivision { a, b } => {
+                write!(f, "{a} is not divisible by {b}")
+            }
+            Self::DivisionByZero { a } => write!(f, "{a} divided by zero"),
+            Self::NoOperands { op } => {
+                write!(f, "'{}' needs at least one number", op.symbol())
+            }
+            Self::OperatorCount { ops, numbers } => {
+                write!(f, "{ops} operators don't fit between {numbers} numbers")
+            }
+            Self::NumberOverflow { row, column } => write!(
+                f,
+                "number overflows at row {}, column {}",
+                row + 1,
+                column + 1
+            ),
+        }
+    }
 }
 
-fn parse_grid_horizontally(input: &str) -> (Grid, Ops) {
-    let rows_count = input.lines().count() - 1;
-    let cols_count = input.lines().next().unwrap().split_whitespace().count();
+impl std::error::Error for EvalError {}
 
-    let grid = Grid::from_shape_vec(
-        (rows_count, cols_count),
-        input
-            .trim_ascii()
-            .split_whitespace()
-            .take(rows_count * cols_count)
-            .map(|n| n.parse().unwrap())
-            .collect_vec(),
-    )
-    .unwrap();
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// An operator cell which isn't a known operator. Column is zero-based.
+    InvalidOperator { column: usize, op: String },
+    /// A problem with no operator below it.
+    MissingOperator { column: usize },
+    /// An operand cell which isn't a digit. Indices are zero-based.
+    InvalidDigit { row: usize, column: usize, byte: u8 },
+}
 
-    (grid, parse_ops(input))
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOperator { column, op } => {
+                write!(f, "invalid operator '{op}' in column {}", column + 1)
+            }
+            Self::MissingOperator { column } => {
+                write!(f, "missing operator for problem at column {}", column + 1)
+            }
+            Self::InvalidDigit { row, column, byte } => write!(
+                f,
+                "invalid digit '{}' in row {}, column {}",
+                byte.escape_ascii(),
+                row + 1,
+                column + 1
+            ),
+        }
+    }
 }
 
-fn transpose<T>(
-    mut iters: Vec<std::vec::IntoIter<T>>,
-) -> impl Iterator<Item = Vec<T>> {
-    std::iter::from_fn(move || iters.iter_mut().map(Iterator::next).collect())
+impl std::error::Error for ParseError {}
+
+/// How the digits of a problem are read into numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reading {
+    /// One number per row.
+    Horizontal,
+    /// One number per column, top to bottom, starting from the rightmost column.
+    Vertical,
 }
 
-fn parse_grid_vertically(input: &str) -> (Grid, Ops) {
-    let rows_count = input.lines().count() - 1;
-    let ops = {
-        let mut ops = parse_ops(input);
-        ops.reverse();
-        ops
-    };
+/// How the operators of a problem combine its numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// A single operator applied across all numbers, left to right except for `^`.
+    Fold,
+    /// Either a single operator, or one operator between each pair of numbers
+    /// (written next to each other, like `+*-`), evaluated left to right with
+    /// precedence: `^` first (grouping to the right),
+    /// then `*` and `/`, then `+` and `-`, then `min` and `max`.
+    Expression,
+}
+
+/// A problem of the worksheet, as written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// Each operand row within the problem's columns, `None` where a cell is blank.
+    /// Every row is as wide as the problem.
+    digits: Vec<Vec<Option<u8>>>,
+    ops: Vec<Op>,
+    column_span: Range<usize>,
+}
 
-    let inverted_rows = input
-        .lines()
-        .take(rows_count)
-        .map(|line| line.chars().rev().collect_vec().into_iter())
-        .collect_vec();
+impl Problem {
+    /// The digits of each operand row, `None` where a cell is blank.
+    #[must_use]
+    pub fn digits(&self) -> &[Vec<Option<u8>>] {
+        &self.digits
+    }
 
-    let mut grid = Grid::zeros((4, 0));
-    let mut curr_col = Vec::with_capacity(4);
+    /// The operators below the problem, usually a single one.
+    #[must_use]
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Columns of the worksheet the problem spans.
+    #[must_use]
+    pub fn column_span(&self) -> Range<usize> {
+        self.column_span.clone()
+    }
 
-    for col in transpose(inverted_rows) {
-        if col.iter().all(char::is_ascii_whitespace) {
-            push_col(&mut curr_col, &mut grid);
+    /// One cell per row or column, in reading order, `None` where it's blank.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first number which doesn't fit in a `u64`.
+    pub fn cells(&self, reading: Reading) -> Result<Vec<Option<u64>>, EvalError> {
+        let start = self.column_span.start;
+        let overflow = |row, column| EvalError::NumberOverflow { row, column };
 
-            continue;
+        match reading {
+            Reading::Horizontal => (0..)
+                .zip(&self.digits)
+                .map(|(row, digits)| {
+                    number(digits.iter().copied())
+                        .map_err(|col| overflow(row, start + col))
+                })
+                .collect(),
+            Reading::Vertical => (0..self.column_span.len())
+                .rev()
+                .map(|col| {
+                    number(self.digits.iter().map(|row| row[col]))
+                        .map_err(|row| overflow(row, start + col))
+                })
+                .collect(),
         }
+    }
 
-        let n = col.iter().filter(|c| !c.is_ascii_whitespace()).fold(
-            0u64,
-            |mut acc, c| {
-                acc *= 10;
-                acc += u64::from(c.to_digit(10).unwrap());
-                acc
-            },
-        );
+    /// The numbers of the problem, in reading order, skipping blank cells.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::cells`].
+    pub fn numbers(&self, reading: Reading) -> Result<Vec<u64>, EvalError> {
+        Ok(self.cells(reading)?.into_iter().flatten().collect())
+    }
 
-        curr_col.push(n);
+    /// The numbers and operators of the problem, without its blank cells and layout.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::cells`].
+    pub fn equation(&self, reading: Reading) -> Result<Equation, EvalError> {
+        Ok(Equation {
+            numbers: self.numbers(reading)?,
+            ops: self.ops.clone(),
+        })
     }
 
-    push_col(&mut curr_col, &mut grid);
+    /// Combines the numbers with the operators. A single operator without any
+    /// numbers gives its identity, for `+` and `*`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a number doesn't fit in a `u64`, if the operators don't fit the
+    /// numbers in `mode`, or if applying them does.
+    pub fn answer(&self, reading: Reading, mode: Mode) -> Result<u64, EvalError> {
+        let numbers = self.numbers(reading)?;
 
-    (grid, ops)
+        match (mode, self.ops.as_slice()) {
+            (_, &[op]) if numbers.is_empty() => match op {
+                Op::Sum => Ok(0),
+                Op::Mul => Ok(1),
+                _ => Err(EvalError::NoOperands { op }),
+            },
+            (_, &[op]) => evaluate(&numbers, &vec![op; numbers.len() - 1]),
+            (Mode::Expression, ops) if ops.len() + 1 == numbers.len() => {
+                evaluate(&numbers, ops)
+            }
+            (_, ops) => Err(EvalError::OperatorCount {
+                ops: ops.len(),
+                numbers: numbers.len(),
+            }),
+        }
+    }
 }
 
-fn push_col(col: &mut Vec<u64>, grid: &mut Grid) {
-    for _ in 0..(grid.nrows() - col.len()) {
-        col.push(0);
+/// Evaluates `numbers[0] ops[0] numbers[1] ops[1] …` by precedence.
+fn evaluate(numbers: &[u64], ops: &[Op]) -> Result<u64, EvalError> {
+    fn reduce(values: &mut Vec<u64>, op: Op) -> Result<(), EvalError> {
+        let (Some(b), Some(a)) = (values.pop(), values.pop()) else {
+            unreachable!()
+        };
+
+        values.push(op.apply(a, b)?);
+
+        Ok(())
+    }
+
+    let mut values = vec![numbers[0]];
+    let mut pending: Vec<Op> = Vec::new();
+
+    for (op, n) in ops.iter().zip(&numbers[1..]) {
+        while let Some(&top) = pending.last()
+            && (top.precedence() > op.precedence()
+                || (top.precedence() == op.precedence() && *op != Op::Pow))
+        {
+            pending.pop();
+            reduce(&mut values, top)?;
+        }
+
+        pending.push(*op);
+        values.push(*n);
     }
 
-    grid.push_column(Array1::from_iter(col.drain(..)).view())
-        .unwrap();
+    while let Some(op) = pending.pop() {
+        reduce(&mut values, op)?;
+    }
+
+    Ok(values[0])
 }
 
-fn parse_ops(input: &str) -> Ops {
-    input
-        .trim_ascii()
-        .lines()
-        .last()
-        .unwrap()
-        .split_whitespace()
-        .map(|s| match s {
-            "+" => Op::Sum,
-            "*" => Op::Mul,
-            _ => unreachable!(),
+/// Reads the digits among `bytes` as a decimal number, `None` if there are none.
+/// Fails with the index of the digit where the number stops fitting in a `u64`.
+fn number(cells: impl Iterator<Item = Option<u8>>) -> Result<Option<u64>, usize> {
+    cells
+        .enumerate()
+        .filter_map(|(i, digit)| Some((i, digit?)))
+        .try_fold(None, |acc: Option<u64>, (i, digit)| {
+            acc.unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|acc| acc.checked_add(u64::from(digit)))
+                .map(Some)
+                .ok_or(i)
         })
-        .collect_vec()
 }
 
-fn solve((grid, ops): (Grid, Ops)) -> u64 {
-    // eprintln!("{grid:?}");
+/// A problem by its numbers and operators alone, without a layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Equation {
+    pub numbers: Vec<u64>,
+    pub ops: Vec<Op>,
+}
+
+impl Equation {
+    /// The operand rows and the operator row of the problem, all as wide as the
+    /// problem, with `rows` operand rows.
+    ///
+    /// Horizontally, numbers are right-aligned, one per row from the top.
+    /// Vertically, they're written from the rightmost column leftwards, with their
+    /// digits aligned to the bottom. The operators are left-aligned.
+    fn layout(&self, reading: Reading, rows: usize) -> (Vec<String>, String) {
+        let ops = self.ops.iter().map(|op| op.symbol()).join("");
+        let numbers = self.numbers.iter().map(u64::to_string).collect_vec();
+
+        let operands = match reading {
+            Reading::Horizontal => {
+                let width = numbers.iter().map(String::len).max().unwrap_or(0);
+                let width = width.max(ops.len());
+
+                (0..rows)
+                    .map(|row| {
+                        let n = numbers.get(row).map_or("", String::as_str);
+
+                        format!("{n:>width$}")
+                    })
+                    .collect_vec()
+            }
+            Reading::Vertical => {
+                let blank = ops.len().saturating_sub(numbers.len());
+                let digit = |n: &String, row: usize| {
+                    (row + n.len())
+                        .checked_sub(rows)
+                        .map_or(' ', |i| char::from(n.as_bytes()[i]))
+                };
+
+                (0..rows)
+                    .map(|row| {
+                        let digits = numbers.iter().rev().map(|n| digit(n, row));
+
+                        " ".repeat(blank) + &digits.collect::<String>()
+                    })
+                    .collect_vec()
+            }
+        };
+
+        let width = operands.first().map_or(ops.len(), String::len);
+
+        (operands, format!("{ops:<width$}"))
+    }
+}
+
+/// Writes `equations` side by side as a worksheet read in `reading`, separated by a
+/// blank column. Parsing it gives the same equations back, as long as each has at
+/// least one operator.
+#[must_use]
+pub fn render(equations: &[Equation], reading: Reading) -> String {
+    let rows = match reading {
+        Reading::Horizontal => equations.iter().map(|eq| eq.numbers.len()).max(),
+        Reading::Vertical => equations
+            .iter()
+            .flat_map(|eq| &eq.numbers)
+            .map(|n| n.to_string().len())
+            .max(),
+    }
+    .unwrap_or(0);
+
+    let (operands, ops): (Vec<_>, Vec<_>) =
+        equations.iter().map(|eq| eq.layout(reading, rows)).unzip();
+
+    (0..rows)
+        .map(|row| operands.iter().map(|lines| &lines[row]).join(" "))
+        .chain([ops.join(" ")])
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// A cephalopod math worksheet: problems side by side, separated by blank columns,
+/// with any number of operand rows above a row of operators.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Worksheet {
+    pub problems: Vec<Problem>,
+}
+
+impl Worksheet {
+    /// Splits the worksheet into problems on the columns which are blank in every row.
+    /// Rows may have different lengths.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first operand cell which isn't a digit, or problem without
+    /// valid operators.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let lines = input.trim_end().lines().collect_vec();
+        let Some((op_row, operand_rows)) = lines.split_last() else {
+            return Ok(Self {
+                problems: Vec::new(),
+            });
+        };
+
+        for (row, line) in operand_rows.iter().enumerate() {
+            if let Some((column, byte)) = line
+                .bytes()
+                .enumerate()
+                .find(|(_, b)| !b.is_ascii_digit() && !b.is_ascii_whitespace())
+            {
+                return Err(ParseError::InvalidDigit { row, column, byte });
+            }
+        }
+
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let cell = |line: &str, col: usize| {
+            line.as_bytes().get(col).copied().unwrap_or(b' ')
+        };
+        let blank = |col: usize| {
+            lines
+                .iter()
+                .all(|line| cell(line, col).is_ascii_whitespace())
+        };
+
+        let problems = (0..width)
+            .chunk_by(|col| blank(*col))
+            .into_iter()
+            .filter(|(blank, _)| !blank)
+            .map(|(_, mut cols)| {
+                let Some(start) = cols.next() else {
+                    unreachable!()
+                };
+                let column_span = start..cols.last().unwrap_or(start) + 1;
+                let text = |line: &str| {
+                    column_span
+                        .clone()
+                        .map(|col| char::from(cell(line, col)))
+                        .collect::<String>()
+                };
+
+                let ops = Op::parse_all(&text(op_row)).map_err(|op| {
+                    ParseError::InvalidOperator {
+                        column: start,
+                        op: op.to_owned(),
+                    }
+                })?;
+
+                if ops.is_empty() {
+                    return Err(ParseError::MissingOperator { column: start });
+                }
+
+                let digits = operand_rows
+                    .iter()
+                    .map(|row| {
+                        column_span
+                            .clone()
+                            .map(|col| parse_digit(cell(row, col), 10))
+                            .collect()
+                    })
+                    .collect();
+
+                Ok(Problem {
+                    digits,
+                    ops,
+                    column_span,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { problems })
+    }
 
-    grid.t()
-        .rows()
-        .into_iter()
-        .zip(ops)
-        .map(|(row, op)| match op {
-            Op::Sum => row.sum(),
-            Op::Mul => row.iter().filter(|n| **n != 0).product(),
+    /// Sum of the answers to every problem.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first problem that does, or if the sum overflows.
+    pub fn grand_total(
+        &self,
+        reading: Reading,
+        mode: Mode,
+    ) -> Result<u64, EvalError> {
+        self.problems.iter().try_fold(0, |total, problem| {
+            Op::Sum.apply(total, problem.answer(reading, mode)?)
         })
-        .sum()
+    }
+
+    /// The equation of every problem, without blank cells and layout.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first number which doesn't fit in a `u64`.
+    pub fn equations(
+        &self,
+        reading: Reading,
+    ) -> Result<Vec<Equation>, EvalError> {
+        self.problems
+            .iter()
+            .map(|problem| problem.equation(reading))
+            .collect()
+    }
+}
+
+#[aoc_generator(day6)]
+fn parse(input: &str) -> Worksheet {
+    Worksheet::parse(input).unwrap()
+}
+
+#[aoc(day6, part1)]
+fn part1(worksheet: &Worksheet) -> u64 {
+    worksheet
+        .grand_total(Reading::Horizontal, Mode::Fold)
+        .unwrap()
+}
+
+#[aoc(day6, part2)]
+fn part2(worksheet: &Worksheet) -> u64 {
+    worksheet
+        .grand_total(Reading::Vertical, Mode::Fold)
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -164,4 +620,423 @@ mod tests {
             11_708_563_470_209
         );
     }
+
+    #[test]
+    fn worksheet() {
+        let worksheet = parse(EXAMPLE);
+
+        assert_eq!(
+            worksheet
+                .problems
+                .iter()
+                .map(Problem::column_span)
+                .collect_vec(),
+            vec![0..3, 4..7, 8..11, 12..15]
+        );
+        assert_eq!(
+            worksheet.problems[0],
+            Problem {
+                digits: vec![
+                    vec![Some(1), Some(2), Some(3)],
+                    vec![None, Some(4), Some(5)],
+                    vec![None, None, Some(6)]
+                ],
+                ops: vec![Op::Mul],
+                column_span: 0..3,
+            }
+        );
+        assert_eq!(
+            worksheet.problems[3].numbers(Reading::Horizontal),
+            Ok(vec![64, 23, 314])
+        );
+        assert_eq!(
+            worksheet.problems[3].numbers(Reading::Vertical),
+            Ok(vec![4, 431, 623])
+        );
+    }
+
+    #[test]
+    fn any_row_count() {
+        let worksheet = parse("1 23\n+ * ");
+
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Horizontal, Mode::Fold)
+                .unwrap(),
+            24
+        );
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Vertical, Mode::Fold)
+                .unwrap(),
+            7
+        );
+
+        let worksheet = parse("1  2\n2  3\n3  4\n4  5\n5  6\n+  *");
+
+        assert_eq!(worksheet.problems.len(), 2);
+        assert_eq!(worksheet.problems[1].digits().len(), 5);
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Horizontal, Mode::Fold)
+                .unwrap(),
+            15 + 720
+        );
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Vertical, Mode::Fold)
+                .unwrap(),
+            12_345 + 23_456
+        );
+    }
+
+    #[test]
+    fn ragged_rows() {
+        // Trailing spaces trimmed, so the last problem is narrower on some rows.
+        let worksheet = parse("12 3\n4  56\n*  +");
+
+        assert_eq!(
+            worksheet
+                .problems
+                .iter()
+                .map(Problem::column_span)
+                .collect_vec(),
+            vec![0..2, 3..5]
+        );
+        assert_eq!(
+            worksheet.problems[1].digits(),
+            [vec![Some(3), None], vec![Some(5), Some(6)]]
+        );
+        assert_eq!(
+            worksheet.problems[1].numbers(Reading::Horizontal),
+            Ok(vec![3, 56])
+        );
+        assert_eq!(
+            worksheet.problems[1].numbers(Reading::Vertical),
+            Ok(vec![6, 35])
+        );
+    }
+
+    #[test]
+    fn zero_operands() {
+        let worksheet = parse("0 5 10\n3 0 02\n* + * ");
+
+        assert_eq!(
+            worksheet.problems[0].numbers(Reading::Horizontal),
+            Ok(vec![0, 3])
+        );
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Horizontal, Mode::Fold)
+                .unwrap(),
+            5 + 20
+        );
+        assert_eq!(
+            worksheet.problems[2].numbers(Reading::Vertical),
+            Ok(vec![2, 10])
+        );
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Vertical, Mode::Fold)
+                .unwrap(),
+            3 + 50 + 20
+        );
+    }
+
+    #[test]
+    fn blank_cells() {
+        let worksheet = parse("12  4\n   56\n 3   \n*  + ");
+
+        assert_eq!(
+            worksheet.problems[0].cells(Reading::Horizontal),
+            Ok(vec![Some(12), None, Some(3)])
+        );
+        assert_eq!(
+            worksheet.problems[1].cells(Reading::Horizontal),
+            Ok(vec![Some(4), Some(56), None])
+        );
+        assert_eq!(
+            worksheet.problems[1].cells(Reading::Vertical),
+            Ok(vec![Some(46), Some(5)])
+        );
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Horizontal, Mode::Fold)
+                .unwrap(),
+            36 + 60
+        );
+        assert_eq!(
+            worksheet
+                .grand_total(Reading::Vertical, Mode::Fold)
+                .unwrap(),
+            23 + 51
+        );
+    }
+
+    #[test]
+    fn operators() {
+        let worksheet = parse(
+            &[
+                "100 2   7  10 3 2",
+                " 20 3   5   4 4 3",
+                "  5 4  12   2 2 2",
+                "-   / min max ^ ^",
+            ]
+            .join("\n"),
+        );
+        let answers = worksheet
+            .problems
+            .iter()
+            .map(|problem| problem.answer(Reading::Horizontal, Mode::Fold))
+            .collect_vec();
+
+        assert_eq!(worksheet.problems[2].ops(), [Op::Min]);
+        assert_eq!(answers[0], Ok(75));
+        assert_eq!(answers[1], Err(EvalError::InexactDivision { a: 2, b: 3 }));
+        assert_eq!(answers[2], Ok(5));
+        assert_eq!(answers[3], Ok(10));
+        assert_eq!(answers[4], Ok(3_u64.pow(16)));
+        assert_eq!(answers[5], Ok(2_u64.pow(9)));
+    }
+
+    #[test]
+    fn apply() {
+        assert_eq!(Op::Div.apply(12, 4), Ok(3));
+        assert_eq!(Op::Div.apply(1, 0), Err(EvalError::DivisionByZero { a: 1 }));
+        assert_eq!(
+            Op::Sub.apply(1, 2),
+            Err(EvalError::Overflow {
+                op: Op::Sub,
+                a: 1,
+                b: 2
+            })
+        );
+        assert_eq!(
+            Op::Mul.apply(u64::MAX, 2),
+            Err(EvalError::Overflow {
+                op: Op::Mul,
+                a: u64::MAX,
+                b: 2
+            })
+        );
+        assert_eq!(Op::Pow.apply(2, 63), Ok(1 << 63));
+        assert!(Op::Pow.apply(2, 64).is_err());
+        assert_eq!(Op::Pow.apply(1, u64::MAX), Ok(1));
+        assert_eq!(Op::Pow.apply(0, 0), Ok(1));
+        assert_eq!(
+            Op::Sum.apply(u64::MAX, 1).unwrap_err().to_string(),
+            "18446744073709551615 + 1 overflows"
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        let worksheet = parse("2\n3\n4\n5\n+*-");
+
+        assert_eq!(worksheet.problems[0].ops(), [Op::Sum, Op::Mul, Op::Sub]);
+        assert_eq!(worksheet.problems[0].column_span(), 0..3);
+        assert_eq!(
+            worksheet.grand_total(Reading::Horizontal, Mode::Fold),
+            Err(EvalError::OperatorCount { ops: 3, numbers: 4 })
+        );
+
+        let answer = |input: &str| {
+            parse(input).problems[0].answer(Reading::Horizontal, Mode::Expression)
+        };
+
+        assert_eq!(answer("2\n3\n4\n5\n+*-"), Ok(2 + 3 * 4 - 5));
+        assert_eq!(answer("20\n 4\n 1\n 2\n/-*"), Ok(20 / 4 - 2));
+        assert_eq!(answer("2\n3\n2\n^^"), Ok(2_u64.pow(9)));
+        assert_eq!(answer("9\n2\n3\n4\nmax*-"), Ok(9));
+        assert_eq!(answer("9\n2\n3\n4\n---"), Ok(0));
+        assert_eq!(answer("9\n2\n3\n4\n-"), Ok(0));
+        assert_eq!(answer("9\n2\n3\n+-"), Ok(8));
+        assert_eq!(answer("1\n2\n3\n+"), Ok(6));
+        assert_eq!(
+            answer("1\n2\n3\n+++"),
+            Err(EvalError::OperatorCount { ops: 3, numbers: 3 })
+        );
+        assert_eq!(
+            answer("2\n5\n-*"),
+            Err(EvalError::OperatorCount { ops: 2, numbers: 2 })
+        );
+    }
+
+    #[test]
+    fn empty_problems() {
+        let answer = |op: &str| {
+            parse(&format!("  \n{op}")).problems[0]
+                .answer(Reading::Vertical, Mode::Fold)
+        };
+
+        assert_eq!(answer("+"), Ok(0));
+        assert_eq!(answer("*"), Ok(1));
+        assert_eq!(answer("min"), Err(EvalError::NoOperands { op: Op::Min }));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Worksheet::parse("1 2\n+ %"),
+            Err(ParseError::InvalidOperator {
+                column: 2,
+                op: "%".into()
+            })
+        );
+        assert_eq!(
+            Worksheet::parse("1 2\n+ -x"),
+            Err(ParseError::InvalidOperator {
+                column: 2,
+                op: "x".into()
+            })
+        );
+        assert_eq!(
+            Worksheet::parse("1 2\n+  "),
+            Err(ParseError::MissingOperator { column: 2 })
+        );
+        assert_eq!(
+            Worksheet::parse("1 2\n3 x\n+ *").unwrap_err().to_string(),
+            "invalid digit 'x' in row 2, column 3"
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let long = Worksheet::parse("1 123456789012345678901234\n+ *").unwrap();
+
+        assert_eq!(
+            long.grand_total(Reading::Horizontal, Mode::Fold),
+            Err(EvalError::NumberOverflow { row: 0, column: 22 })
+        );
+        assert_eq!(long.grand_total(Reading::Vertical, Mode::Fold), Ok(1));
+
+        let tall = Worksheet::parse(&("9\n".repeat(21) + "+")).unwrap();
+
+        assert_eq!(tall.grand_total(Reading::Horizontal, Mode::Fold), Ok(189));
+        assert_eq!(
+            tall.grand_total(Reading::Vertical, Mode::Fold)
+                .unwrap_err()
+                .to_string(),
+            "number overflows at row 20, column 1"
+        );
+        assert_eq!(
+            tall.equations(Reading::Vertical),
+            Err(EvalError::NumberOverflow { row: 19, column: 0 })
+        );
+
+        let max = Worksheet::parse(&format!("{}\n+", u64::MAX)).unwrap();
+
+        assert_eq!(
+            max.problems[0].numbers(Reading::Horizontal),
+            Ok(vec![u64::MAX])
+        );
+        assert_eq!(
+            Worksheet::parse(&format!("{}0\n*", u64::MAX))
+                .unwrap()
+                .problems[0]
+                .cells(Reading::Horizontal),
+            Err(EvalError::NumberOverflow { row: 0, column: 20 })
+        );
+    }
+
+    #[test]
+    fn render_layouts() {
+        let equations = parse(EXAMPLE).equations(Reading::Horizontal).unwrap();
+
+        assert_eq!(
+            render(&equations, Reading::Horizontal),
+            "123 328  51  64\n 45  64 387  23\n  6  98 215 314\n*   +   *   +  \n"
+        );
+        assert_eq!(
+            render(&equations, Reading::Vertical),
+            [
+                "  1   3 23  3  ",
+                " 42 962 185 126",
+                "653 848 571 434",
+                "*   +   *   +  "
+            ]
+            .map(|line| line.to_owned() + "\n")
+            .concat()
+        );
+
+        let equations = [
+            Equation {
+                numbers: vec![],
+                ops: vec![Op::Max],
+            },
+            Equation {
+                numbers: vec![7, 10],
+                ops: vec![Op::Sub, Op::Min, Op::Pow],
+            },
+        ];
+
+        assert_eq!(
+            render(&equations, Reading::Horizontal),
+            "        7\n       10\nmax -min^\n"
+        );
+        assert_eq!(
+            render(&equations, Reading::Vertical),
+            "       1 \n       07\nmax -min^\n"
+        );
+    }
+
+    #[test]
+    fn render_round_trip() {
+        for input in [EXAMPLE, include_str!("../input/2025/day6.txt")] {
+            for reading in [Reading::Horizontal, Reading::Vertical] {
+                let equations = parse(input).equations(reading).unwrap();
+                let rendered = render(&equations, reading);
+
+                assert_eq!(
+                    parse(&rendered).equations(reading).unwrap(),
+                    equations
+                );
+            }
+
+            // Rewriting the part 1 problems vertically turns them into part 2 ones.
+            let worksheet = parse(input);
+            let vertical = parse(&render(
+                &worksheet.equations(Reading::Horizontal).unwrap(),
+                Reading::Vertical,
+            ));
+
+            assert_eq!(part2(&vertical), part1(&worksheet));
+        }
+    }
+
+    #[test]
+    fn random_round_trip() {
+        use rand::prelude::*;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..500 {
+            let equations = (0..rng.random_range(0..6))
+                .map(|_| {
+                    let numbers = (0..rng.random_range(0..5))
+                        .map(|_| {
+                            let digits = rng.random_range(1..8);
+
+                            rng.random_range(0..10_u64.pow(digits))
+                        })
+                        .collect_vec();
+                    let ops = (0..rng.random_range(1..=numbers.len().max(1)))
+                        .map(|_| *Op::ALL.choose(&mut rng).unwrap())
+                        .collect();
+
+                    Equation { numbers, ops }
+                })
+                .collect_vec();
+
+            for reading in [Reading::Horizontal, Reading::Vertical] {
+                let rendered = render(&equations, reading);
+
+                assert_eq!(
+                    parse(&rendered).equations(reading).unwrap(),
+                    equations,
+                    "{rendered}"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,324 @@
+//! Digit helpers for unsigned integers of any width, in any radix between 2 and 36.
+
+use std::ops::{Add, Div, Mul, Rem};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Unsigned integer types whose digits can be inspected.
+pub trait Unsigned:
+    sealed::Sealed
+    + Copy
+    + Ord
+    + From<u8>
+    + Add<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + TryInto<u32>
+{
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+            impl Unsigned for $t {}
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128, usize);
+
+fn radix<T: Unsigned>(radix: u32) -> T {
+    assert!(
+        (2..=36).contains(&radix),
+        "radix must be between 2 and 36, got {radix}"
+    );
+
+    T::from(u8::try_from(radix).unwrap())
+}
+
+/// Number of digits of `n` written in `radix`. Zero has a single digit.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn count_digits<T: Unsigned>(mut n: T, radix: u32) -> u32 {
+    let radix = self::radix::<T>(radix);
+    let mut count = 1;
+
+    while n >= radix {
+        n = n / radix;
+        count += 1;
+    }
+
+    count
+}
+
+/// Value of an ASCII digit in `radix`, accepting both lower and upper case letters.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn parse_digit(byte: u8, radix: u32) -> Option<u8> {
+    assert!(
+        (2..=36).contains(&radix),
+        "radix must be between 2 and 36, got {radix}"
+    );
+
+    char::from(byte)
+        .to_digit(radix)
+        .map(|d| u8::try_from(d).unwrap())
+}
+
+/// Splits a number into equally sized chunks of digits.
+///
+/// Iterating forward yields the most significant chunk first, iterating backwards
+/// (`.rev()`) the least significant one first. Each chunk is the value of its digits
+/// read in the usual order, e.g. `123_456` in two chunks is `[123, 456]`.
+#[derive(Clone, Debug)]
+pub struct DigitChunks<T> {
+    n: T,
+    /// `radix ^ chunk_len`, only meaningful with two or more chunks left.
+    unit: T,
+    /// `radix ^ (chunk_len * (remaining - 1))`.
+    pow: T,
+    remaining: u32,
+}
+
+impl<T: Unsigned> DigitChunks<T> {
+    /// Returns `None` if the digits of `n` cannot be split in `chunks` chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not between 2 and 36.
+    #[must_use]
+    pub fn new(n: T, radix: u32, chunks: u32) -> Option<Self> {
+        let digits = count_digits(n, radix);
+
+        if chunks == 0 || !digits.is_multiple_of(chunks) {
+            return None;
+        }
+
+        let radix = self::radix::<T>(radix);
+        let one = T::from(1);
+
+        // With a single chunk `radix ^ chunk_len` might not fit in `T`, but it's
+        // also never needed.
+        let unit = if chunks > 1 {
+            pow(radix, digits / chunks)
+        } else {
+            one
+        };
+
+        Some(Self {
+            n,
+            unit,
+            pow: pow(radix, digits - digits / chunks),
+            remaining: chunks,
+        })
+    }
+}
+
+/// Iterates over the digits of `n` written in `radix`, most significant first.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn digits<T: Unsigned>(n: T, radix: u32) -> DigitChunks<T> {
+    DigitChunks::new(n, radix, count_digits(n, radix)).unwrap()
+}
+
+/// Writes `n` in `radix`, using lower case letters for digits above 9.
+///
+/// # Panics
+///
+/// Panics if `radix` is not between 2 and 36.
+#[must_use]
+pub fn to_string_radix<T: Unsigned>(n: T, radix: u32) -> String {
+    digits(n, radix)
+        .map(|d| {
+            let Ok(d) = d.try_into() else { unreachable!() };
+
+            char::from_digit(d, radix).unwrap()
+        })
+        .collect()
+}
+
+fn pow<T: Unsigned>(base: T, exp: u32) -> T {
+    (0..exp).fold(T::from(1), |acc, _| acc * base)
+}
+
+impl<T: Unsigned> Iterator for DigitChunks<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let chunk = self.n / self.pow;
+
+        self.n = self.n % self.pow;
+        self.remaining -= 1;
+
+        if self.remaining > 0 {
+            self.pow = self.pow / self.unit;
+        }
+
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining as usize;
+
+        (len, Some(len))
+    }
+}
+
+impl<T: Unsigned> DoubleEndedIterator for DigitChunks<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.remaining {
+            0 => None,
+            1 => {
+                self.remaining = 0;
+
+                Some(self.n)
+            }
+            _ => {
+                let chunk = self.n % self.unit;
+
+                self.n = self.n / self.unit;
+                self.pow = self.pow / self.unit;
+                self.remaining -= 1;
+
+                Some(chunk)
+            }
+        }
+    }
+}
+
+impl<T: Unsigned> ExactSizeIterator for DigitChunks<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    macro_rules! run {
+        ($n:expr, $radix:expr, $chunks:expr) => {
+            DigitChunks::new($n, $radix, $chunks).unwrap().collect_vec()
+        };
+    }
+
+    macro_rules! run_rev {
+        ($n:expr, $radix:expr, $chunks:expr) => {
+            DigitChunks::new($n, $radix, $chunks)
+                .unwrap()
+                .rev()
+                .collect_vec()
+        };
+    }
+
+    #[test]
+    fn count_digits_() {
+        assert_eq!(count_digits(0u64, 10), 1);
+        assert_eq!(count_digits(9u64, 10), 1);
+        assert_eq!(count_digits(10u64, 10), 2);
+        assert_eq!(count_digits(u8::MAX, 10), 3);
+        assert_eq!(count_digits(u8::MAX, 2), 8);
+        assert_eq!(count_digits(u8::MAX, 16), 2);
+        assert_eq!(count_digits(u64::MAX, 10), 20);
+        assert_eq!(count_digits(u128::MAX, 10), 39);
+        assert_eq!(count_digits(u128::MAX, 36), 25);
+    }
+
+    #[test]
+    fn chunks() {
+        assert_eq!(vec![0], run!(0u64, 10, 1));
+        assert_eq!(vec![12], run!(12u64, 10, 1));
+        assert_eq!(vec![1, 2], run!(12u64, 10, 2));
+        assert_eq!(vec![12, 34], run!(1_234u64, 10, 2));
+        assert_eq!(vec![123, 456], run!(123_456u64, 10, 2));
+        assert_eq!(vec![10, 0], run!(1_000u64, 10, 2));
+        assert_eq!(vec![0xab, 0xcd], run!(0xabcdu32, 16, 2));
+        assert_eq!(vec![0b10, 0b01, 0b11], run!(0b10_01_11u8, 2, 3));
+        assert!(DigitChunks::new(123u64, 10, 2).is_none());
+        assert!(DigitChunks::new(123u64, 10, 0).is_none());
+    }
+
+    #[test]
+    fn rev_chunks() {
+        assert_eq!(vec![0], run_rev!(0u64, 10, 1));
+        assert_eq!(vec![12], run_rev!(12u64, 10, 1));
+        assert_eq!(vec![2, 1], run_rev!(12u64, 10, 2));
+        assert_eq!(vec![34, 12], run_rev!(1_234u64, 10, 2));
+        assert_eq!(vec![456, 123], run_rev!(123_456u64, 10, 2));
+        assert_eq!(vec![0xcd, 0xab], run_rev!(0xabcdu32, 16, 2));
+    }
+
+    #[test]
+    fn chunks_at_type_limits() {
+        assert_eq!(vec![u8::MAX], run!(u8::MAX, 10, 1));
+        assert_eq!(vec![u8::MAX], run_rev!(u8::MAX, 10, 1));
+        assert_eq!(vec![u128::MAX], run!(u128::MAX, 10, 1));
+        assert_eq!(vec![u128::MAX], run_rev!(u128::MAX, 10, 1));
+        assert_eq!(
+            vec![3_402_823_669_209, 3_846_346_337_460, 7_431_768_211_455],
+            run!(u128::MAX, 10, 3)
+        );
+        assert_eq!(
+            vec![7_431_768_211_455, 3_846_346_337_460, 3_402_823_669_209],
+            run_rev!(u128::MAX, 10, 3)
+        );
+    }
+
+    #[test]
+    fn mixed_directions() {
+        let mut iter = DigitChunks::new(123_456_789u64, 10, 3).unwrap();
+
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back(), Some(789));
+        assert_eq!(iter.next(), Some(123));
+        assert_eq!(iter.next_back(), Some(456));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn digits_() {
+        assert_eq!(digits(1_203u32, 10).collect_vec(), vec![1, 2, 0, 3]);
+        assert_eq!(digits(0xf0u8, 16).collect_vec(), vec![0xf, 0]);
+        assert_eq!(digits(5u16, 2).rev().collect_vec(), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn parse_digit_() {
+        assert_eq!(parse_digit(b'7', 10), Some(7));
+        assert_eq!(parse_digit(b'a', 10), None);
+        assert_eq!(parse_digit(b'a', 16), Some(10));
+        assert_eq!(parse_digit(b'Z', 36), Some(35));
+        assert_eq!(parse_digit(b'2', 2), None);
+    }
+
+    #[test]
+    fn to_string_radix_() {
+        assert_eq!(to_string_radix(0u8, 10), "0");
+        assert_eq!(to_string_radix(1_203u32, 10), "1203");
+        assert_eq!(to_string_radix(0xbeefu16, 16), "beef");
+        assert_eq!(to_string_radix(5usize, 2), "101");
+        assert_eq!(to_string_radix(35u64, 36), "z");
+        assert_eq!(to_string_radix(u128::MAX, 10), u128::MAX.to_string());
+    }
+
+    #[test]
+    #[should_panic = "radix must be between 2 and 36"]
+    fn invalid_radix() {
+        let _ = count_digits(1u8, 37);
+    }
+}
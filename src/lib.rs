@@ -1,15 +1,18 @@
+pub mod coverage;
 mod day1;
 mod day10;
 mod day11;
 mod day12;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
 mod day7;
 mod day8;
 mod day9;
-mod rangeset;
+pub mod digits;
+pub mod rangemap;
+pub mod rangeset;
 
 aoc_runner_derive::aoc_lib! { year = 2025 }
@@ -0,0 +1,459 @@
+use crate::rangeset::{Discrete, half_open};
+use std::ops::{Range, RangeBounds, RangeInclusive};
+
+/// Maps non-overlapping ranges of keys to values, the [`RangeSet`] counterpart.
+///
+/// Inserting a range overwrites the part of existing entries it overlaps, splitting
+/// them if needed. Adjacent entries with equal values are merged. Entries are kept
+/// sorted.
+///
+/// The last entry may be unbounded, mapping every key from its start on. That's how
+/// a range ending at the maximum of `K` is stored, like in [`RangeSet`].
+///
+/// [`RangeSet`]: crate::rangeset::RangeSet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeMap<K, V> {
+    entries: Vec<(Range<K>, V)>,
+    unbounded: Option<(K, V)>,
+}
+
+impl<K, V> Default for RangeMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            unbounded: None,
+        }
+    }
+}
+
+impl<K: Ord + Copy, V: Clone + PartialEq> RangeMap<K, V> {
+    /// Maps every key in `range` to `value`. Empty ranges are ignored.
+    pub fn insert_range(&mut self, range: Range<K>, value: V) {
+        if range.is_empty() {
+            return;
+        }
+
+        self.split_unbounded(&range);
+
+        let idx = self.cut(&range);
+
+        self.entries.insert(idx, (range, value));
+        self.coalesce(idx);
+        self.coalesce_unbounded();
+    }
+
+    /// Maps every key from `start` on to `value`.
+    pub fn insert_from(&mut self, start: K, value: V) {
+        self.cut_from(start);
+        self.unbounded = Some((start, value));
+        self.coalesce_unbounded();
+    }
+
+    /// Unmaps every key in `range`.
+    pub fn remove_range(&mut self, range: &Range<K>) {
+        if !range.is_empty() {
+            self.split_unbounded(range);
+            self.cut(range);
+        }
+    }
+
+    /// Unmaps every key from `start` on.
+    pub fn remove_from(&mut self, start: K) {
+        self.cut_from(start);
+    }
+
+    /// The value `key` maps to, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match &self.unbounded {
+            Some((from, value)) if from <= key => Some(value),
+            _ => self.get_key_value(key).map(|(_, value)| value),
+        }
+    }
+
+    /// The bounded entry containing `key`, if any.
+    pub fn get_key_value(&self, key: &K) -> Option<(&Range<K>, &V)> {
+        let idx = self.entries.partition_point(|(range, _)| range.end <= *key);
+
+        self.entries
+            .get(idx)
+            .filter(|(range, _)| range.start <= *key)
+            .map(|(range, value)| (range, value))
+    }
+
+    /// The bounded entries, in order. See [`Self::unbounded`] for the last one.
+    pub fn iter(&self) -> impl Iterator<Item = (&Range<K>, &V)> {
+        self.entries.iter().map(|(range, value)| (range, value))
+    }
+
+    /// Start and value of the unbounded entry at the end of the map, if any.
+    pub fn unbounded(&self) -> Option<(&K, &V)> {
+        self.unbounded.as_ref().map(|(from, value)| (from, value))
+    }
+
+    /// The entries overlapping `range`, in order, clipped to it.
+    pub fn range(&self, range: Range<K>) -> impl Iterator<Item = (Range<K>, &V)> {
+        let first = self.entries.partition_point(|(x, _)| x.end <= range.start);
+        let unbounded = self
+            .unbounded
+            .iter()
+            .filter(move |(from, _)| *from < range.end)
+            .map(move |(from, value)| {
+                ((*from).max(range.start)..range.end, value)
+            });
+
+        self.entries[first..]
+            .iter()
+            .take_while(move |(x, _)| x.start < range.end)
+            .map(move |(x, value)| {
+                (x.start.max(range.start)..x.end.min(range.end), value)
+            })
+            .chain(unbounded)
+    }
+
+    /// Number of entries, the unbounded one included.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len() + self.unbounded.is_some() as usize
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.unbounded.is_none()
+    }
+
+    /// Moves the start of the unbounded entry past `range`, keeping the part of it
+    /// before `range` as a bounded entry.
+    fn split_unbounded(&mut self, range: &Range<K>) {
+        if let Some((from, value)) = &mut self.unbounded
+            && range.end > *from
+        {
+            if range.start > *from {
+                self.entries.push((*from..range.start, value.clone()));
+            }
+
+            *from = range.end;
+        }
+    }
+
+    /// Removes `range` from the bounded entries, keeping the pieces on either side of
+    /// it. Returns where an entry for `range` would go.
+    fn cut(&mut self, range: &Range<K>) -> usize {
+        let first = self.entries.partition_point(|(x, _)| x.end <= range.start);
+        let last = self.entries.partition_point(|(x, _)| x.start < range.end);
+
+        if first >= last {
+            return first;
+        }
+
+        let (head, head_value) = &self.entries[first];
+        let head = (head.start..range.start, head_value.clone());
+        let (tail, tail_value) = &self.entries[last - 1];
+        let tail = (range.end..tail.end, tail_value.clone());
+        let idx = first + usize::from(!head.0.is_empty());
+
+        self.entries.splice(
+            first..last,
+            [head, tail].into_iter().filter(|(x, _)| !x.is_empty()),
+        );
+
+        idx
+    }
+
+    /// Removes everything from `start` on, keeping the pieces before it.
+    fn cut_from(&mut self, start: K) {
+        let idx = self.entries.partition_point(|(x, _)| x.start < start);
+
+        self.entries.truncate(idx);
+
+        if let Some((last, _)) = self.entries.last_mut()
+            && last.end > start
+        {
+            last.end = start;
+        }
+
+        if let Some((from, value)) = self.unbounded.take()
+            && from < start
+        {
+            self.entries.push((from..start, value));
+        }
+    }
+
+    /// Merges the entry at `idx` with its neighbours if they touch and are equal.
+    fn coalesce(&mut self, mut idx: usize) {
+        if idx > 0 && self.mergeable(idx - 1) {
+            idx -= 1;
+            self.entries[idx].0.end = self.entries.remove(idx + 1).0.end;
+        }
+
+        if self.mergeable(idx) {
+            self.entries[idx].0.end = self.entries.remove(idx + 1).0.end;
+        }
+    }
+
+    /// Merges the last bounded entry into the unbounded one if they touch and are
+    /// equal.
+    fn coalesce_unbounded(&mut self) {
+        let Some((from, value)) = &mut self.unbounded else {
+            return;
+        };
+
+        if let Some((last, x)) = self.entries.last()
+            && last.end == *from
+            && x == value
+        {
+            *from = last.start;
+            self.entries.pop();
+        }
+    }
+
+    fn mergeable(&self, idx: usize) -> bool {
+        match (self.entries.get(idx), self.entries.get(idx + 1)) {
+            (Some((a, x)), Some((b, y))) if a.end == b.start => x == y,
+            _ => false,
+        }
+    }
+}
+
+impl<K: Discrete, V: Clone + PartialEq> RangeMap<K, V> {
+    /// Maps any kind of range to `value`, e.g. `a..=b` or `a..`, including ones
+    /// ending at `K::MAX`. Empty ranges are ignored.
+    pub fn insert(&mut self, range: impl RangeBounds<K>, value: V) {
+        match half_open(&range) {
+            Some((start, Some(end))) => self.insert_range(start..end, value),
+            Some((start, None)) => self.insert_from(start, value),
+            None => {}
+        }
+    }
+
+    /// Unmaps any kind of range, see [`Self::insert`].
+    pub fn remove(&mut self, range: impl RangeBounds<K>) {
+        match half_open(&range) {
+            Some((start, Some(end))) => self.remove_range(&(start..end)),
+            Some((start, None)) => self.remove_from(start),
+            None => {}
+        }
+    }
+}
+
+impl<K: Ord + Copy, V: Clone + PartialEq> FromIterator<(Range<K>, V)>
+    for RangeMap<K, V>
+{
+    fn from_iter<I: IntoIterator<Item = (Range<K>, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+
+        for (range, value) in iter {
+            map.insert_range(range, value);
+        }
+
+        map
+    }
+}
+
+impl<K: Discrete, V: Clone + PartialEq> FromIterator<(RangeInclusive<K>, V)>
+    for RangeMap<K, V>
+{
+    fn from_iter<I: IntoIterator<Item = (RangeInclusive<K>, V)>>(
+        iter: I,
+    ) -> Self {
+        let mut map = Self::default();
+
+        for (range, value) in iter {
+            map.insert(range, value);
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::prelude::*;
+    use std::ops::Bound;
+
+    fn entries<V: Clone + PartialEq>(
+        map: &RangeMap<u8, V>,
+    ) -> Vec<(Range<u8>, V)> {
+        map.iter()
+            .map(|(range, value)| (range.clone(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut map = RangeMap::default();
+
+        map.insert(0..10, 'a');
+        map.insert(20..30, 'b');
+        assert_eq!(entries(&map), vec![(0..10, 'a'), (20..30, 'b')]);
+
+        map.insert(5..25, 'c');
+        assert_eq!(
+            entries(&map),
+            vec![(0..5, 'a'), (5..25, 'c'), (25..30, 'b')]
+        );
+
+        map.insert(10..12, 'd');
+        assert_eq!(
+            entries(&map),
+            vec![
+                (0..5, 'a'),
+                (5..10, 'c'),
+                (10..12, 'd'),
+                (12..25, 'c'),
+                (25..30, 'b')
+            ]
+        );
+
+        map.insert(10..12, 'c');
+        assert_eq!(
+            entries(&map),
+            vec![(0..5, 'a'), (5..25, 'c'), (25..30, 'b')]
+        );
+
+        map.insert(30..35, 'b');
+        map.insert(3..5, 'c');
+        assert_eq!(
+            entries(&map),
+            vec![(0..3, 'a'), (3..25, 'c'), (25..35, 'b')]
+        );
+
+        #[allow(clippy::reversed_empty_ranges)]
+        map.insert(8..2, 'z');
+        map.insert(0..40, 'e');
+        assert_eq!(entries(&map), vec![(0..40, 'e')]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map: RangeMap<u8, char> =
+            [(0..10, 'a'), (10..20, 'b')].into_iter().collect();
+
+        map.remove(5..15);
+        assert_eq!(entries(&map), vec![(0..5, 'a'), (15..20, 'b')]);
+
+        map.remove(0..2);
+        map.remove(30..40);
+        assert_eq!(entries(&map), vec![(2..5, 'a'), (15..20, 'b')]);
+
+        map.remove(0..40);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let map: RangeMap<u8, char> =
+            [(0..10, 'a'), (10..20, 'b'), (25..30, 'c')]
+                .into_iter()
+                .collect();
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&0), Some(&'a'));
+        assert_eq!(map.get(&10), Some(&'b'));
+        assert_eq!(map.get(&20), None);
+        assert_eq!(map.get(&29), Some(&'c'));
+        assert_eq!(map.get(&30), None);
+        assert_eq!(map.get_key_value(&15), Some((&(10..20), &'b')));
+
+        assert_eq!(
+            map.range(5..27).collect_vec(),
+            vec![(5..10, &'a'), (10..20, &'b'), (25..27, &'c')]
+        );
+        assert_eq!(map.range(20..25).collect_vec(), vec![]);
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut map = RangeMap::default();
+
+        map.insert(250..=u8::MAX, 'a');
+        assert_eq!(map.unbounded(), Some((&250, &'a')));
+        assert_eq!(map.get(&u8::MAX), Some(&'a'));
+        assert_eq!(map.get(&249), None);
+
+        map.insert(252..254, 'b');
+        assert_eq!(entries(&map), vec![(250..252, 'a'), (252..254, 'b')]);
+        assert_eq!(map.unbounded(), Some((&254, &'a')));
+        assert_eq!(map.len(), 3);
+
+        map.insert(240..=252, 'a');
+        assert_eq!(entries(&map), vec![(240..253, 'a'), (253..254, 'b')]);
+
+        map.insert(253..254, 'a');
+        assert_eq!(entries(&map), vec![]);
+        assert_eq!(map.unbounded(), Some((&240, &'a')));
+        assert_eq!(map.range(200..245).collect_vec(), vec![(240..245, &'a')]);
+
+        map.remove(245..);
+        assert_eq!(entries(&map), vec![(240..245, 'a')]);
+        assert_eq!(map.unbounded(), None);
+
+        map.insert(.., 'c');
+        assert_eq!(map.len(), 1);
+        map.remove(..=9);
+        map.remove(20..30);
+        assert_eq!(entries(&map), vec![(10..20, 'c')]);
+        assert_eq!(map.unbounded(), Some((&30, &'c')));
+
+        let map: RangeMap<u64, usize> =
+            [(0..=u64::MAX, 0), (u64::MAX..=u64::MAX, 1)]
+                .into_iter()
+                .collect();
+
+        assert_eq!(map.get(&(u64::MAX - 1)), Some(&0));
+        assert_eq!(map.get(&u64::MAX), Some(&1));
+    }
+
+    #[test]
+    fn random_inserts() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let mut map = RangeMap::default();
+            let mut model = [None; 256];
+
+            for _ in 0..rng.random_range(0..10) {
+                let start = rng.random_range(200..=u8::MAX);
+                let end = rng.random_range(start..=u8::MAX);
+                let (end, model_end) = match rng.random_range(0..3) {
+                    0 => (Bound::Excluded(end), usize::from(end)),
+                    1 => (Bound::Included(end), usize::from(end) + 1),
+                    _ => (Bound::Unbounded, 256),
+                };
+                let range = (Bound::Included(start), end);
+                let keys = usize::from(start)..model_end;
+                let value = rng.random_range(0..3);
+
+                if rng.random_bool(0.8) {
+                    model[keys].fill(Some(value));
+                    map.insert(range, value);
+                } else {
+                    model[keys].fill(None);
+                    map.remove(range);
+                }
+            }
+
+            assert!(
+                map.iter().tuple_windows().all(|((a, x), (b, y))| {
+                    let touching = a.end == b.start;
+                    a.end <= b.start && !(touching && x == y)
+                }),
+                "{map:?}"
+            );
+            assert!(map.iter().all(|(range, _)| !range.is_empty()));
+            assert!(
+                map.iter()
+                    .last()
+                    .zip(map.unbounded())
+                    .is_none_or(|((a, x), (from, y))| a.end < *from
+                        || a.end == *from && x != y),
+                "{map:?}"
+            );
+
+            for (key, value) in (0..=u8::MAX).zip(model) {
+                assert_eq!(map.get(&key), value.as_ref(), "{key} {map:?}");
+            }
+        }
+    }
+}
@@ -1,7 +1,13 @@
 // Adapted from https://github.com/dlight/todomd/blob/d9ef5920097a089bba553130711b29d50cc7d6b6/common/todomd/src/rangeset.rs
 
 use itertools::{Itertools, Position};
-use std::ops::{Bound, Range};
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::iter::Sum;
+use std::ops::{
+    BitAnd, BitOr, BitXor, Bound, Range, RangeBounds, RangeInclusive, Sub,
+};
+use std::str::FromStr;
 
 trait RangeExt {
     /// Merge ranges. If ranges are adjacent or overlapping, returns a single range that covers them. If they are not, returns None.
@@ -10,33 +16,128 @@ trait RangeExt {
         Self: Sized;
 }
 
-impl<T: PartialOrd + Ord + Copy> RangeExt for Range<T> {
+impl<T: Ord + Clone> RangeExt for Range<T> {
     fn merge_range(&self, range: &Self) -> Option<Self> {
         assert!(!self.is_empty());
         assert!(!range.is_empty());
 
-        let start = self.start.min(range.start);
-        let end = self.end.max(range.end);
-
         if self.end < range.start || range.end < self.start {
             None
         } else {
-            Some(start..end)
+            let start = (&self.start).min(&range.start);
+            let end = (&self.end).max(&range.end);
+
+            Some(start.clone()..end.clone())
         }
     }
 }
 
+/// Values with a successor and a predecessor, so that inclusive and unbounded ranges
+/// can be stored as half-open ones.
+pub trait Discrete: Copy + Ord {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// `self + 1`, or `None` at `MAX`.
+    fn successor(self) -> Option<Self>;
+
+    /// `self - 1`, or `None` at `MIN`.
+    fn predecessor(self) -> Option<Self>;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn successor(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn predecessor(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+/// Discrete values with a wider type that can count all of them.
+pub trait Countable: Discrete {
+    type Count: Copy + Ord + Debug + Sum;
+
+    /// Number of values in `start..end`, or in `start..` without an `end`.
+    fn count(start: Self, end: Option<Self>) -> Self::Count;
+}
+
+macro_rules! impl_countable {
+    ($($t:ty => $wide:ty),*) => {
+        $(
+            impl Countable for $t {
+                type Count = $wide;
+
+                #[allow(clippy::cast_lossless)]
+                fn count(start: Self, end: Option<Self>) -> $wide {
+                    end.map_or((<$t>::MAX - start) as $wide + 1, |end| {
+                        (end - start) as $wide
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_countable!(u8 => u16, u16 => u32, u32 => u64, u64 => u128, usize => u128);
+
 /// A set of non-overlapping, non-adjacent ranges. Inserting new ranges will merge them if
 /// possible. Ranges are kept sorted.
-#[derive(Debug, Default)]
+///
+/// The last range may be unbounded, covering every value from `unbounded_from` on.
+/// That's how a range ending at the maximum of `T` is stored, since its exclusive end
+/// can't be represented.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RangeSet<T> {
     contents: Vec<Range<T>>,
+    unbounded_from: Option<T>,
+}
+
+impl<T> Default for RangeSet<T> {
+    fn default() -> Self {
+        Self {
+            contents: Vec::new(),
+            unbounded_from: None,
+        }
+    }
 }
 
-impl<T: PartialOrd + Copy + Ord + Clone> RangeSet<T> {
+impl<T: Ord + Clone> RangeSet<T> {
+    /// Inserts `range`, merging it with the ranges it overlaps or touches. Empty
+    /// ranges are ignored.
+    // The ranges merged with it always overlap or touch it, so merging can't fail.
+    #[allow(clippy::missing_panics_doc)]
     pub fn insert_range(&mut self, range: &Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        if self
+            .unbounded_from
+            .as_ref()
+            .is_some_and(|from| range.end >= *from)
+        {
+            self.insert_from(range.start.clone());
+            return;
+        }
+
         if self.contents.is_empty() {
             self.contents.push(range.clone());
+            self.debug_assert_invariants();
             return;
         }
 
@@ -75,32 +176,545 @@ impl<T: PartialOrd + Copy + Ord + Clone> RangeSet<T> {
         }
 
         self.contents.splice((start, end), [new_range]);
+        self.debug_assert_invariants();
+    }
+
+    /// Inserts `start..`, merging every range that reaches it.
+    pub fn insert_from(&mut self, start: T) {
+        let idx = self.contents.partition_point(|x| x.end < start);
+        let start = match self.contents.get(idx) {
+            Some(x) if x.start < start => x.start.clone(),
+            _ => start,
+        };
+
+        self.contents.truncate(idx);
+        self.unbounded_from = Some(match self.unbounded_from.take() {
+            Some(from) => from.min(start),
+            None => start,
+        });
+        self.debug_assert_invariants();
+    }
+
+    /// All the ranges, in order, as bounds. The unbounded one comes last.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.into_iter()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+    /// The bounded ranges, in order. See [`Self::unbounded_from`] for the last one.
+    pub fn iter_bounded(&self) -> impl Iterator<Item = &Range<T>> {
         self.contents.iter()
     }
+
+    /// Start of the unbounded range at the end of the set, if any.
+    pub const fn unbounded_from(&self) -> Option<&T> {
+        self.unbounded_from.as_ref()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.contents.is_empty() && self.unbounded_from.is_none()
+    }
+
+    /// Checks that the ranges are non-empty, sorted, and neither overlap nor touch
+    /// each other or the unbounded range.
+    ///
+    /// # Panics
+    ///
+    /// If any of these doesn't hold.
+    pub fn assert_invariants(&self) {
+        for (i, range) in self.contents.iter().enumerate() {
+            assert!(!range.is_empty(), "range {i} is empty");
+        }
+
+        for (i, (a, b)) in self.contents.iter().tuple_windows().enumerate() {
+            assert!(
+                a.end < b.start,
+                "ranges {i} and {} overlap, touch or are out of order",
+                i + 1
+            );
+        }
+
+        if let (Some(last), Some(from)) =
+            (self.contents.last(), &self.unbounded_from)
+        {
+            assert!(
+                last.end < *from,
+                "last range overlaps or touches the unbounded one"
+            );
+        }
+    }
+
+    /// [`Self::assert_invariants`], in debug builds only. Every method building or
+    /// modifying a set calls it before returning.
+    pub fn debug_assert_invariants(&self) {
+        if cfg!(debug_assertions) {
+            self.assert_invariants();
+        }
+    }
+
+    /// Whether `value` is in any of the ranges, the unbounded one included, which
+    /// [`Self::find`] leaves out.
+    pub fn contains(&self, value: &T) -> bool {
+        self.unbounded_from
+            .as_ref()
+            .is_some_and(|from| from <= value)
+            || self.find(value).is_some()
+    }
+
+    /// The bounded range containing `value`, if any. Values in the unbounded range
+    /// give `None`, see [`Self::unbounded_from`].
+    pub fn find(&self, value: &T) -> Option<&Range<T>> {
+        let idx = self.contents.partition_point(|range| range.end <= *value);
+
+        self.contents.get(idx).filter(|range| range.start <= *value)
+    }
+
+    /// Counts how many of `values` are in the set, sweeping through both in a single
+    /// pass. `values` must be sorted.
+    pub fn count_contained<I>(&self, values: I) -> usize
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        let mut ranges = self.contents.iter().peekable();
+
+        values
+            .into_iter()
+            .filter(|value| {
+                let value = value.borrow();
+
+                while ranges.next_if(|range| range.end <= *value).is_some() {}
+
+                ranges.peek().is_some_and(|range| range.start <= *value)
+                    || self
+                        .unbounded_from
+                        .as_ref()
+                        .is_some_and(|from| from <= value)
+            })
+            .count()
+    }
+
+    /// Removes `range` from the set, splitting the ranges it falls within.
+    pub fn remove_range(&mut self, range: &Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        if let Some(from) = &self.unbounded_from
+            && range.end > *from
+        {
+            if range.start > *from {
+                self.contents.push(from.clone()..range.start.clone());
+            }
+
+            self.unbounded_from = Some(range.end.clone());
+        }
+
+        let first = self.contents.partition_point(|x| x.end <= range.start);
+        let last = self.contents.partition_point(|x| x.start < range.end);
+
+        if first < last {
+            let head = self.contents[first].start.clone()..range.start.clone();
+            let tail = range.end.clone()..self.contents[last - 1].end.clone();
+
+            self.contents.splice(
+                first..last,
+                [head, tail].into_iter().filter(|r| !r.is_empty()),
+            );
+        }
+
+        self.debug_assert_invariants();
+    }
+
+    /// Removes `start..`, cutting the set short.
+    pub fn remove_from(&mut self, start: T) {
+        let idx = self.contents.partition_point(|x| x.start < start);
+
+        self.contents.truncate(idx);
+
+        if let Some(last) = self.contents.last_mut()
+            && last.end > start
+        {
+            last.end = start.clone();
+        }
+
+        if let Some(from) = self.unbounded_from.take()
+            && from < start
+        {
+            self.contents.push(from..start);
+        }
+
+        self.debug_assert_invariants();
+    }
+
+    /// Values in either set.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a || b)
+    }
+
+    /// Values in both sets.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a && b)
+    }
+
+    /// Values in `self` but not in `other`.
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a && !b)
+    }
+
+    /// Values in exactly one of the sets.
+    #[must_use]
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a != b)
+    }
+
+    /// Values in `bounds` but not in the set.
+    #[must_use]
+    pub fn complement(&self, bounds: &Range<T>) -> Self {
+        let bounds = Self {
+            contents: std::iter::once(bounds.clone())
+                .filter(|r| !r.is_empty())
+                .collect(),
+            unbounded_from: None,
+        };
+
+        bounds.difference(self)
+    }
+
+    /// Sweeps through the boundaries of both sets in order, keeping the values for
+    /// which `keep(in self, in other)` holds.
+    fn combine(&self, other: &Self, keep: impl Fn(bool, bool) -> bool) -> Self {
+        let mut a = self.boundaries().peekable();
+        let mut b = other.boundaries().peekable();
+        let (mut in_a, mut in_b) = (false, false);
+        let mut start = None;
+        let mut contents = Vec::new();
+
+        loop {
+            let point = match (a.peek(), b.peek()) {
+                (None, None) => break,
+                (Some(x), None) => *x,
+                (None, Some(y)) => *y,
+                (Some(x), Some(y)) => (*x).min(*y),
+            };
+
+            // Ranges within a set are never adjacent, so each point toggles each set
+            // at most once.
+            if a.next_if_eq(&point).is_some() {
+                in_a = !in_a;
+            }
+
+            if b.next_if_eq(&point).is_some() {
+                in_b = !in_b;
+            }
+
+            match (start, keep(in_a, in_b)) {
+                (None, true) => start = Some(point),
+                (Some(from), false) => {
+                    contents.push(from.clone()..point.clone());
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        let set = Self {
+            contents,
+            unbounded_from: start.cloned(),
+        };
+
+        set.debug_assert_invariants();
+        set
+    }
+
+    fn boundaries(&self) -> impl Iterator<Item = &T> {
+        self.contents
+            .iter()
+            .flat_map(|range| [&range.start, &range.end])
+            .chain(&self.unbounded_from)
+    }
 }
 
-#[cfg(test)]
-impl<T: PartialOrd + Copy + Ord + Default> From<Vec<Range<T>>> for RangeSet<T> {
+macro_rules! impl_set_op {
+    ($trait:ident, $fn:ident, $method:ident) => {
+        impl<T: Ord + Clone> $trait<&RangeSet<T>> for &RangeSet<T> {
+            type Output = RangeSet<T>;
+
+            fn $fn(self, rhs: &RangeSet<T>) -> RangeSet<T> {
+                self.$method(rhs)
+            }
+        }
+
+        impl<T: Ord + Clone> $trait for RangeSet<T> {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                self.$method(&rhs)
+            }
+        }
+    };
+}
+
+impl_set_op!(BitOr, bitor, union);
+impl_set_op!(BitAnd, bitand, intersection);
+impl_set_op!(Sub, sub, difference);
+impl_set_op!(BitXor, bitxor, symmetric_difference);
+
+impl<T: Discrete> RangeSet<T> {
+    /// Inserts any kind of range, e.g. `a..=b` or `a..`, including ones ending at
+    /// `T::MAX`. Empty ranges are ignored.
+    pub fn insert(&mut self, range: impl RangeBounds<T>) {
+        match half_open(&range) {
+            Some((start, Some(end))) => self.insert_range(&(start..end)),
+            Some((start, None)) => self.insert_from(start),
+            None => {}
+        }
+    }
+
+    /// Removes any kind of range, see [`Self::insert`].
+    pub fn remove(&mut self, range: impl RangeBounds<T>) {
+        match half_open(&range) {
+            Some((start, Some(end))) => self.remove_range(&(start..end)),
+            Some((start, None)) => self.remove_from(start),
+            None => {}
+        }
+    }
+
+    /// The ranges as inclusive ones, in order, including the unbounded one.
+    pub fn iter_inclusive(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.contents
+            .iter()
+            .filter_map(|range| Some(range.start..=range.end.predecessor()?))
+            .chain(self.unbounded_from.map(|from| from..=T::MAX))
+    }
+}
+
+/// `range` as `start..end`, with no `end` when it reaches past `T::MAX`, or `None`
+/// if it starts past it.
+pub(crate) fn half_open<T: Discrete>(
+    range: &impl RangeBounds<T>,
+) -> Option<(T, Option<T>)> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.successor()?,
+        Bound::Unbounded => T::MIN,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.successor(),
+        Bound::Excluded(&end) => Some(end),
+        Bound::Unbounded => None,
+    };
+
+    Some((start, end))
+}
+
+impl<T: Countable> RangeSet<T> {
+    /// Number of values in the set, in a type wide enough to count all of them.
+    pub fn len(&self) -> T::Count {
+        self.contents
+            .iter()
+            .map(|range| T::count(range.start, Some(range.end)))
+            .chain(self.unbounded_from.map(|from| T::count(from, None)))
+            .sum()
+    }
+}
+
+impl<T: Ord + Clone> From<Vec<Range<T>>> for RangeSet<T> {
     fn from(vec: Vec<Range<T>>) -> Self {
         vec.into_iter().collect()
     }
 }
 
-#[cfg(test)]
-impl<T: PartialOrd + Copy + Ord + Clone + Default> FromIterator<Range<T>>
-    for RangeSet<T>
-{
+impl<T: Ord + Clone> FromIterator<Range<T>> for RangeSet<T> {
     fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
         let mut range = Self::default();
+        range.extend(iter);
+        range
+    }
+}
 
+impl<T: Discrete> FromIterator<RangeInclusive<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut range = Self::default();
+        range.extend(iter);
+        range
+    }
+}
+
+impl<T: Ord + Clone> Extend<Range<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
         for v in iter {
-            range.insert_range(&v);
+            self.insert_range(&v);
         }
+    }
+}
 
-        range
+impl<T: Discrete> Extend<RangeInclusive<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = RangeInclusive<T>>>(&mut self, iter: I) {
+        for v in iter {
+            self.insert(v);
+        }
+    }
+}
+
+/// Iterates over all the ranges as bounds, the unbounded one included.
+impl<T> IntoIterator for RangeSet<T> {
+    type Item = (Bound<T>, Bound<T>);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            bounded: self.contents.into_iter(),
+            unbounded_from: self.unbounded_from,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RangeSet<T> {
+    type Item = (Bound<&'a T>, Bound<&'a T>);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter {
+            bounded: self.contents.iter(),
+            unbounded_from: self.unbounded_from.as_ref(),
+        }
+    }
+}
+
+/// Owning iterator over the ranges of a [`RangeSet`], as bounds.
+#[derive(Clone, Debug)]
+pub struct IntoIter<T> {
+    bounded: std::vec::IntoIter<Range<T>>,
+    unbounded_from: Option<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Bound<T>, Bound<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.bounded.next() {
+            Some(range) => {
+                Some((Bound::Included(range.start), Bound::Excluded(range.end)))
+            }
+            None => self
+                .unbounded_from
+                .take()
+                .map(|from| (Bound::Included(from), Bound::Unbounded)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bounded.len() + usize::from(self.unbounded_from.is_some());
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+/// Borrowing iterator over the ranges of a [`RangeSet`], as bounds.
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    bounded: std::slice::Iter<'a, Range<T>>,
+    unbounded_from: Option<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Bound<&'a T>, Bound<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.bounded.next() {
+            Some(range) => {
+                Some((Bound::Included(&range.start), Bound::Excluded(&range.end)))
+            }
+            None => self
+                .unbounded_from
+                .take()
+                .map(|from| (Bound::Included(from), Bound::Unbounded)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bounded.len() + usize::from(self.unbounded_from.is_some());
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+/// Renders the set as `[0..3, 5..15, 20..]`.
+impl<T: fmt::Display> fmt::Display for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bounded = self
+            .contents
+            .iter()
+            .map(|range| format!("{}..{}", range.start, range.end));
+        let unbounded =
+            self.unbounded_from.iter().map(|from| format!("{from}.."));
+
+        write!(f, "[{}]", bounded.chain(unbounded).join(", "))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError<E> {
+    /// The input isn't wrapped in square brackets.
+    Brackets,
+    /// An item which isn't `start..end` or `start..`.
+    InvalidRange(String),
+    InvalidValue(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ParseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Brackets => {
+                write!(f, "range set must be wrapped in '[' and ']'")
+            }
+            Self::InvalidRange(range) => write!(f, "invalid range '{range}'"),
+            Self::InvalidValue(err) => write!(f, "invalid value: {err}"),
+        }
+    }
+}
+
+impl<E: Debug + fmt::Display> std::error::Error for ParseError<E> {}
+
+/// Parses the format written by `Display`. Ranges may overlap and come in any order.
+impl<T: FromStr + Ord + Clone> FromStr for RangeSet<T> {
+    type Err = ParseError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s
+            .trim()
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or(ParseError::Brackets)?
+            .trim();
+        let mut set = Self::default();
+
+        if inner.is_empty() {
+            return Ok(set);
+        }
+
+        for item in inner.split(',').map(str::trim) {
+            let (start, end) = item
+                .split_once("..")
+                .ok_or_else(|| ParseError::InvalidRange(item.to_owned()))?;
+            let value =
+                |s: &str| s.parse::<T>().map_err(ParseError::InvalidValue);
+            let start = value(start)?;
+
+            if end.is_empty() {
+                set.insert_from(start);
+            } else {
+                set.insert_range(&(start..value(end)?));
+            }
+        }
+
+        Ok(set)
     }
 }
 
@@ -109,6 +723,7 @@ impl<T: PartialOrd + Copy + Ord + Clone + Default> FromIterator<Range<T>>
 mod tests {
     use super::*;
     use rand::prelude::*;
+    use std::collections::BTreeSet;
 
     #[test]
     fn test_merge_ranges() {
@@ -229,4 +844,522 @@ mod tests {
             vec![1..50]
         );
     }
+
+    #[test]
+    fn test_insert_range_inclusive() {
+        let mut set = RangeSet::default();
+
+        set.insert(3..=5);
+        set.insert(10..=14);
+        set.insert(16..=20);
+        set.insert(12..=18);
+        set.insert(6..=6);
+        #[allow(clippy::reversed_empty_ranges)]
+        set.insert(30..=29);
+
+        assert_eq!(set.contents, vec![3u64..7, 10..21]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let set = RangeSet::from(vec![0..3, 5..15, 20..21]);
+
+        assert!(set.contains(&0));
+        assert!(set.contains(&2));
+        assert!(!set.contains(&3));
+        assert!(!set.contains(&4));
+        assert!(set.contains(&5));
+        assert!(set.contains(&14));
+        assert!(!set.contains(&15));
+        assert!(set.contains(&20));
+        assert!(!set.contains(&21));
+        assert!(!RangeSet::<u8>::default().contains(&0));
+
+        assert_eq!(set.find(&7), Some(&(5..15)));
+        assert_eq!(set.find(&20), Some(&(20..21)));
+        assert_eq!(set.find(&17), None);
+    }
+
+    #[test]
+    fn test_count_contained() {
+        let set = RangeSet::from(vec![0..3, 5..15, 20..21]);
+
+        assert_eq!(set.count_contained([0, 1, 2, 3, 4, 5, 20, 21, 30]), 5);
+        assert_eq!(set.count_contained([4, 4, 5, 5]), 2);
+        assert_eq!(set.count_contained([15u32, 16].iter()), 0);
+        assert_eq!(RangeSet::default().count_contained([1, 2]), 0);
+    }
+
+    #[test]
+    fn random_membership() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..200 {
+            let ranges = (0..rng.random_range(0..20))
+                .map(|_| {
+                    let start = rng.random_range(0..1000);
+                    start..start + rng.random_range(1..50)
+                })
+                .collect_vec();
+            let set = RangeSet::from(ranges.clone());
+            let values = (0..100)
+                .map(|_| rng.random_range(0..1100))
+                .sorted()
+                .collect_vec();
+
+            for value in &values {
+                assert_eq!(
+                    set.contains(value),
+                    ranges.iter().any(|range| range.contains(value))
+                );
+                assert_eq!(
+                    set.find(value).is_some_and(|range| range.contains(value)),
+                    set.contains(value)
+                );
+            }
+
+            assert_eq!(
+                set.count_contained(&values),
+                values.iter().filter(|value| set.contains(value)).count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let mut set = RangeSet::from(vec![0..3, 5..15, 20..25]);
+
+        set.remove_range(&(7..9));
+        assert_eq!(set.contents, vec![0..3, 5..7, 9..15, 20..25]);
+
+        set.remove_range(&(2..6));
+        assert_eq!(set.contents, vec![0..2, 6..7, 9..15, 20..25]);
+
+        set.remove_range(&(15..20));
+        assert_eq!(set.contents, vec![0..2, 6..7, 9..15, 20..25]);
+
+        set.remove_range(&(6..30));
+        assert_eq!(set.contents, vec![0..2]);
+
+        set.remove_range(&(0..2));
+        assert_eq!(set.contents, vec![]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = RangeSet::from(vec![0..5, 10..15]);
+        let b = RangeSet::from(vec![3..12, 15..20]);
+
+        assert_eq!((&a | &b).contents, vec![0..20]);
+        assert_eq!((&a & &b).contents, vec![3..5, 10..12]);
+        assert_eq!((&a - &b).contents, vec![0..3, 12..15]);
+        assert_eq!((&b - &a).contents, vec![5..10, 15..20]);
+        assert_eq!((&a ^ &b).contents, vec![0..3, 5..10, 12..20]);
+        assert_eq!(a.complement(&(2..30)).contents, vec![5..10, 15..30]);
+        assert_eq!(a.complement(&(6..8)).contents, vec![6..8]);
+        assert_eq!(a.complement(&(1..3)).contents, vec![]);
+        assert_eq!((a ^ b).contents, vec![0..3, 5..10, 12..20]);
+    }
+
+    fn model(set: &RangeSet<u8>) -> BTreeSet<u8> {
+        set.iter_bounded().flat_map(Clone::clone).collect()
+    }
+
+    fn random_set(rng: &mut impl Rng) -> (RangeSet<u8>, BTreeSet<u8>) {
+        let mut set = RangeSet::default();
+        let mut model = BTreeSet::new();
+
+        for _ in 0..rng.random_range(0..8) {
+            let start = rng.random_range(0..100);
+            let range = start..start + rng.random_range(1..20);
+
+            set.insert_range(&range);
+            model.extend(range);
+        }
+
+        (set, model)
+    }
+
+    #[test]
+    fn random_set_operations() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let (a, model_a) = random_set(&mut rng);
+            let (b, model_b) = random_set(&mut rng);
+
+            let results = [
+                (&a | &b, model_a.union(&model_b).copied().collect()),
+                (&a & &b, model_a.intersection(&model_b).copied().collect()),
+                (&a - &b, model_a.difference(&model_b).copied().collect()),
+                (
+                    &a ^ &b,
+                    model_a.symmetric_difference(&model_b).copied().collect(),
+                ),
+                (
+                    a.complement(&(10..90)),
+                    (10..90).filter(|v| !model_a.contains(v)).collect(),
+                ),
+            ];
+
+            for (set, expected) in results {
+                set.assert_invariants();
+                assert_eq!(model(&set), expected, "{a:?} {b:?}");
+            }
+
+            let mut removed = a.clone();
+            let start = rng.random_range(0..110);
+            let range = start..start + rng.random_range(0..30);
+
+            removed.remove_range(&range);
+
+            removed.assert_invariants();
+            assert_eq!(
+                model(&removed),
+                model_a
+                    .iter()
+                    .filter(|v| !range.contains(*v))
+                    .copied()
+                    .collect()
+            );
+        }
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut set = RangeSet::default();
+
+        set.insert(u64::MAX - 1..=u64::MAX);
+        set.insert(u64::MAX - 3..u64::MAX - 1);
+        set.insert(0..=2);
+
+        assert_eq!(set.contents, vec![0..3]);
+        assert_eq!(set.unbounded_from(), Some(&(u64::MAX - 3)));
+        assert!(set.contains(&u64::MAX));
+        assert_eq!(set.count_contained([1, 3, u64::MAX - 3, u64::MAX]), 3);
+        assert_eq!(set.len(), 7);
+        assert_eq!(
+            set.iter_inclusive().collect_vec(),
+            vec![0..=2, u64::MAX - 3..=u64::MAX]
+        );
+
+        set.insert(1..);
+        assert_eq!(set.contents, vec![]);
+        assert_eq!(set.unbounded_from(), Some(&0));
+        assert_eq!(set.len(), 1 << 64);
+
+        set.remove(5..u64::MAX);
+        assert_eq!(set.contents, vec![0..5]);
+        assert_eq!(set.unbounded_from(), Some(&u64::MAX));
+        assert_eq!(set.len(), 6);
+
+        set.remove(3..);
+        assert_eq!(set.contents, vec![0..3]);
+        assert_eq!(set.unbounded_from(), None);
+
+        set.remove(..);
+        assert!(set.is_empty());
+
+        let mut set = RangeSet::default();
+        set.insert(..);
+        assert_eq!(set.len(), 1 << 64);
+        assert_eq!(
+            (&set - &RangeSet::from(vec![1..u64::MAX]))
+                .iter_inclusive()
+                .collect_vec(),
+            vec![0..=0, u64::MAX..=u64::MAX]
+        );
+        assert_eq!(RangeSet::<usize>::default().len(), 0);
+    }
+
+    #[test]
+    fn random_bounds() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let mut set = RangeSet::default();
+            let mut model = BTreeSet::new();
+
+            for _ in 0..rng.random_range(0..10) {
+                let start = rng.random_range(200..=u8::MAX);
+                let end = rng.random_range(start..=u8::MAX);
+                let range = (
+                    if rng.random_bool(0.2) {
+                        Bound::Unbounded
+                    } else {
+                        Bound::Included(start)
+                    },
+                    if rng.random_bool(0.2) {
+                        Bound::Unbounded
+                    } else if rng.random_bool(0.5) {
+                        Bound::Excluded(end)
+                    } else {
+                        Bound::Included(end)
+                    },
+                );
+                let values = (0..=u8::MAX).filter(|v| range.contains(v));
+
+                if rng.random_bool(0.7) {
+                    set.insert(range);
+                    model.extend(values);
+                } else {
+                    set.remove(range);
+                    values.for_each(|v| {
+                        model.remove(&v);
+                    });
+                }
+            }
+
+            let inclusive = set.iter_inclusive().collect_vec();
+
+            assert!(
+                inclusive
+                    .iter()
+                    .tuple_windows()
+                    .all(|(a, b)| *a.end() < b.start() - 1),
+                "{set:?}"
+            );
+            assert_eq!(
+                inclusive.into_iter().flatten().collect::<BTreeSet<_>>(),
+                model
+            );
+            assert_eq!(set.len(), u16::try_from(model.len()).unwrap());
+            assert!(
+                (0..=u8::MAX).all(|v| set.contains(&v) == model.contains(&v))
+            );
+        }
+    }
+
+    #[test]
+    fn test_collect_and_iterate() {
+        let mut set: RangeSet<u32> = [5..10, 0..3, 8..15].into_iter().collect();
+        set.extend([20..25, 25..30]);
+
+        assert_eq!(set, RangeSet::from(vec![0..3, 5..15, 20..30]));
+        assert_eq!(
+            set.clone().into_iter().collect_vec(),
+            vec![
+                (Bound::Included(0), Bound::Excluded(3)),
+                (Bound::Included(5), Bound::Excluded(15)),
+                (Bound::Included(20), Bound::Excluded(30))
+            ]
+        );
+
+        set.insert(40..);
+
+        assert_eq!((&set).into_iter().len(), 4);
+        assert_eq!(set.iter().collect_vec(), (&set).into_iter().collect_vec());
+        assert_eq!(set.iter_bounded().count(), 3);
+        assert!(set.contains(&50));
+        assert_eq!(set.find(&50), None);
+        assert_eq!(
+            (&set).into_iter().last(),
+            Some((Bound::Included(&40), Bound::Unbounded))
+        );
+        assert_eq!(
+            set.clone().into_iter().last(),
+            Some((Bound::Included(40), Bound::Unbounded))
+        );
+
+        let mut unbounded = RangeSet::<u8>::default();
+        unbounded.insert(0..);
+
+        assert!(unbounded.contains(&5));
+        assert_eq!(
+            unbounded.into_iter().collect_vec(),
+            vec![(Bound::Included(0), Bound::Unbounded)]
+        );
+
+        let mut inclusive: RangeSet<u8> =
+            [0..=2, 250..=255].into_iter().collect();
+        inclusive.extend([3..=4]);
+
+        assert_eq!(inclusive.contents, vec![0..5]);
+        assert_eq!(inclusive.unbounded_from(), Some(&250));
+        assert_ne!(inclusive, RangeSet::from(vec![0..5]));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(RangeSet::<u8>::default().to_string(), "[]");
+        assert_eq!(
+            RangeSet::from(vec![0..3, 5..15]).to_string(),
+            "[0..3, 5..15]"
+        );
+
+        let mut set = RangeSet::from(vec![0u8..3]);
+        set.insert(200..);
+        assert_eq!(set.to_string(), "[0..3, 200..]");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("[]".parse(), Ok(RangeSet::<u8>::default()));
+        assert_eq!(
+            " [ 5..15,0..3 , 2..4 ] ".parse(),
+            Ok(RangeSet::from(vec![0u32..4, 5..15]))
+        );
+        assert_eq!(
+            "[0..3, 200..]".parse::<RangeSet<u8>>().map(|set| set.len()),
+            Ok(59)
+        );
+
+        assert_eq!("0..3".parse::<RangeSet<u8>>(), Err(ParseError::Brackets));
+        assert_eq!(
+            "[0..3, 4]".parse::<RangeSet<u8>>(),
+            Err(ParseError::InvalidRange("4".to_owned()))
+        );
+        assert!(matches!(
+            "[0..300]".parse::<RangeSet<u8>>(),
+            Err(ParseError::InvalidValue(_))
+        ));
+        assert_eq!(
+            "[0..3, x]".parse::<RangeSet<u8>>().unwrap_err().to_string(),
+            "invalid range 'x'"
+        );
+    }
+
+    #[test]
+    fn random_display_round_trip() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let (mut set, _) = random_set(&mut rng);
+
+            if rng.random_bool(0.3) {
+                set.insert(rng.random_range(0..=u8::MAX)..);
+            }
+
+            assert_eq!(set.to_string().parse(), Ok(set));
+        }
+    }
+
+    #[test]
+    fn test_assert_invariants() {
+        let valid = RangeSet::from(vec![0..3, 5..15]);
+        valid.assert_invariants();
+
+        for contents in [vec![0..3, 3..5], vec![5..8, 0..3], vec![0..3, 4..4]] {
+            let invalid = RangeSet {
+                contents,
+                unbounded_from: None,
+            };
+            let result = std::panic::catch_unwind(|| invalid.assert_invariants());
+
+            assert!(result.is_err(), "{invalid:?}");
+        }
+
+        let touching = RangeSet {
+            contents: vec![0..3],
+            unbounded_from: Some(3),
+        };
+
+        assert!(
+            std::panic::catch_unwind(|| touching.assert_invariants()).is_err()
+        );
+    }
+
+    #[test]
+    fn random_inserts_bitset() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..1000 {
+            let mut set = RangeSet::default();
+            let mut model = [false; 64];
+
+            for _ in 0..rng.random_range(1..20) {
+                let start = rng.random_range(0..64);
+                let range = start..rng.random_range(start..=64);
+
+                set.insert_range(&range);
+                model[range.clone()].fill(true);
+
+                set.assert_invariants();
+                assert!(
+                    (0..64).all(|v| set.contains(&v) == model[v]),
+                    "{set:?} after {range:?}"
+                );
+            }
+
+            let covered = set.iter_bounded().flat_map(Clone::clone).collect_vec();
+
+            assert_eq!(covered, (0..64).filter(|v| model[*v]).collect_vec());
+        }
+    }
+
+    #[test]
+    fn random_inserts_near_max() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let low = usize::MAX - 200;
+
+        for _ in 0..500 {
+            let mut set = RangeSet::default();
+            let mut model = BTreeSet::new();
+
+            for _ in 0..rng.random_range(1..20) {
+                let start = rng.random_range(low..=usize::MAX);
+                let end = rng.random_range(start..=usize::MAX);
+
+                if rng.random_bool(0.5) {
+                    set.insert_range(&(start..end));
+                    model.extend(start..end);
+                } else {
+                    set.insert(start..=end);
+                    model.extend(start..=end);
+                }
+
+                set.assert_invariants();
+                assert_eq!(
+                    set.iter_inclusive().flatten().collect::<BTreeSet<_>>(),
+                    model,
+                    "{set:?}"
+                );
+            }
+
+            assert_eq!(set.len(), model.len() as u128);
+            assert_eq!(set.contains(&usize::MAX), model.contains(&usize::MAX));
+        }
+    }
+
+    #[test]
+    fn test_non_copy_keys() {
+        let sku = |s: &str| s.to_owned();
+        let mut set = RangeSet::default();
+
+        set.insert_range(&(sku("apple")..sku("banana")));
+        set.insert_range(&(sku("cherry")..sku("grape")));
+        set.insert_range(&(sku("banana")..sku("beet")));
+        set.insert_from(sku("pear"));
+        set.assert_invariants();
+
+        assert!(set.contains(&sku("avocado")));
+        assert!(set.contains(&sku("banana")));
+        assert!(!set.contains(&sku("beet")));
+        assert!(set.contains(&sku("zucchini")));
+        assert_eq!(set.unbounded_from(), Some(&sku("pear")));
+        assert_eq!(set.to_string(), "[apple..beet, cherry..grape, pear..]");
+        assert_eq!(set.to_string().parse(), Ok(set.clone()));
+
+        let other = RangeSet::from(vec![sku("b")..sku("d"), sku("q")..sku("r")]);
+
+        assert_eq!((&set & &other).to_string(), "[b..beet, cherry..d, q..r]");
+        assert_eq!(
+            (&set - &other).to_string(),
+            "[apple..b, d..grape, pear..q, r..]"
+        );
+
+        set.remove_range(&(sku("berry")..sku("date")));
+        set.remove_from(sku("plum"));
+        assert_eq!(set.to_string(), "[apple..beet, date..grape, pear..plum]");
+
+        let mut dates = RangeSet::default();
+
+        dates.insert_range(&((2025, 12, 1)..(2025, 12, 13)));
+        dates.insert_range(&((2025, 11, 20)..(2025, 12, 1)));
+
+        assert_eq!(
+            dates.iter_bounded().collect_vec(),
+            vec![&((2025, 11, 20)..(2025, 12, 13))]
+        );
+    }
 }